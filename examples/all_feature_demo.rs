@@ -39,8 +39,8 @@ fn run() -> Result<()> {
 
     // let mut led = SysfsRgbLed::new("redLed", "grnLed", "bluLed").unwrap();
     // loop {
-    //     for i in 0..255u8 {
-    //         led.set_color(Color::from_hsl(i, 128, 32)).unwrap();
+    //     for color in colors::hue_wheel(255, 255) {
+    //         led.set_color(color).unwrap();
     //     }
     // }
 
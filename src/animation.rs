@@ -0,0 +1,90 @@
+// Copyright (c) 2017 Nick Stevens <nick@bitcurry.com>
+
+//! Timed color sequences for RGB LEDs
+
+use std::thread;
+use std::time::Duration;
+
+use crate::colors::Color;
+use crate::errors::*;
+use crate::RgbLed;
+
+/// How many times an `Animation` plays through its steps
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Repeat {
+    /// Play through the steps a single time
+    Once,
+    /// Play through the steps the given number of times
+    Count(u32),
+    /// Play through the steps until an error occurs
+    Forever,
+}
+
+/// A sequence of colors, each shown for a fixed duration
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Animation {
+    steps: Vec<(Color, Duration)>,
+    repeat: Repeat,
+}
+
+impl Animation {
+    /// Create a new `Animation` from `(Color, Duration)` steps
+    pub fn new(steps: Vec<(Color, Duration)>, repeat: Repeat) -> Animation {
+        Animation {
+            steps: steps,
+            repeat: repeat,
+        }
+    }
+
+    /// Play the animation on an RGB LED
+    ///
+    /// Each step's color is set and then the current thread sleeps for the
+    /// step's duration. This blocks until every repetition has played, which
+    /// for `Repeat::Forever` means it only returns on error. An animation with
+    /// no steps returns immediately.
+    pub fn play<L: RgbLed>(&self, led: &mut L) -> Result<()> {
+        if self.steps.is_empty() {
+            return Ok(());
+        }
+        let mut remaining = match self.repeat {
+            Repeat::Once => Some(1),
+            Repeat::Count(count) => Some(count),
+            Repeat::Forever => None,
+        };
+        while remaining != Some(0) {
+            for &(color, duration) in &self.steps {
+                led.set_color(color)?;
+                thread::sleep(duration);
+            }
+            remaining = remaining.map(|count| count - 1);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors;
+    use crate::testing::MockLed;
+
+    #[test]
+    fn test_play() {
+        let steps = vec![(colors::RED, Duration::from_millis(1)),
+                         (colors::GREEN, Duration::from_millis(1)),
+                         (colors::BLUE, Duration::from_millis(1))];
+        let mut led = MockLed::new();
+        Animation::new(steps.clone(), Repeat::Count(2)).play(&mut led).expect("play animation");
+        assert_eq!(&[colors::RED, colors::GREEN, colors::BLUE,
+                     colors::RED, colors::GREEN, colors::BLUE],
+                   led.color_history());
+
+        let mut led = MockLed::new();
+        Animation::new(steps, Repeat::Once).play(&mut led).expect("play animation");
+        assert_eq!(&[colors::RED, colors::GREEN, colors::BLUE], led.color_history());
+
+        let mut led = MockLed::new();
+        Animation::new(vec![], Repeat::Forever).play(&mut led).expect("play empty animation");
+        assert!(led.color_history().is_empty());
+    }
+}
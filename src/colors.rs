@@ -2,7 +2,12 @@
 
 //! Colorspace tools for RGB LEDs
 
-use std::cmp;
+use core::cmp;
+use core::ops::{Add, Sub};
+
+#[cfg(feature = "std")]
+use crate::errors::*;
+use crate::math;
 
 /// RGB Black
 pub const BLACK: Color = Color(0, 0, 0);
@@ -21,6 +26,107 @@ pub const CYAN: Color = Color(0, 255, 255);
 /// RGB Magenta
 pub const MAGENTA: Color = Color(255, 0, 255);
 
+/// Sweep all the way around the color wheel
+///
+/// Yields 256 colors, stepping hue from 0 through 255 at a fixed `saturation`
+/// and `value` (see `Color::from_hsv`), for cycling an LED through a rainbow.
+pub fn hue_wheel(saturation: u8, value: u8) -> impl Iterator<Item = Color> {
+    (0..=255u8).map(move |hue| Color::from_hsv(hue, saturation, value))
+}
+
+/// Temporal dithering toward a fractional brightness level
+///
+/// An LED with few brightness levels, down to one that can only be switched on
+/// and off, can show an in-between level by alternating from frame to frame.
+/// `Dither` decides whether each frame is on, spreading the on frames evenly
+/// so that the on-ratio over any run of frames stays close to the target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dither {
+    level: f32,
+}
+
+impl Dither {
+    /// Create a new `Dither` for a target level from 0.0 (off) to 1.0 (on)
+    ///
+    /// Levels outside that range are clamped, and NaN is treated as 0.0.
+    pub fn new(level: f32) -> Dither {
+        let level = if level.is_nan() { 0.0 } else { level.max(0.0).min(1.0) };
+        Dither { level: level }
+    }
+
+    /// Get the target level
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+
+    /// Check whether the LED should be on during the given frame
+    pub fn is_on(&self, frame: u64) -> bool {
+        let level = self.level as f64;
+        math::floor((frame + 1) as f64 * level) > math::floor(frame as f64 * level)
+    }
+}
+
+/// Models for reducing a `Color` to a single brightness value
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MonoWeighting {
+    /// Rec. 601 luma weights, as used by standard-definition video
+    Rec601,
+    /// Rec. 709 luma weights, as used by `Color::luminance`
+    Rec709,
+    /// Unweighted mean of the three channels
+    Average,
+    /// Brightest of the three channels
+    Max,
+}
+
+/// Modes for compositing one `Color` over another with `Color::blend_mode`
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlendMode {
+    /// The top color replaces the base
+    Normal,
+    /// Channels are multiplied, which can only darken
+    Multiply,
+    /// Inverted channels are multiplied, which can only lighten
+    Screen,
+    /// Multiply dark base channels and screen light ones, adding contrast
+    Overlay,
+    /// Channels are summed, saturating at 255
+    Add,
+}
+
+/// Semantic states for status indicators
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Status {
+    /// Everything is fine (green)
+    Ok,
+    /// Something needs attention (amber)
+    Warn,
+    /// Something has failed (red)
+    Error,
+    /// Informational (blue)
+    Info,
+}
+
+/// Get the standard indicator `Color` for a `Status`
+pub fn status(state: Status) -> Color {
+    match state {
+        Status::Ok => GREEN,
+        Status::Warn => Color(255, 191, 0),
+        Status::Error => RED,
+        Status::Info => BLUE,
+    }
+}
+
+// Named colors, in declaration order, for lookup by name
+const NAMED_COLORS: [(&'static str, Color); 8] = [("black", BLACK),
+                                                  ("white", WHITE),
+                                                  ("red", RED),
+                                                  ("green", GREEN),
+                                                  ("blue", BLUE),
+                                                  ("yellow", YELLOW),
+                                                  ("cyan", CYAN),
+                                                  ("magenta", MAGENTA)];
+
 /// Representation of color in RGB colorspace
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Color(u8, u8, u8);
@@ -31,6 +137,277 @@ impl Color {
         Color(red, green, blue)
     }
 
+    /// Create a new gray `Color` with every channel at `level`
+    ///
+    /// The counterpart to `luminance` for single-channel effects.
+    pub fn gray(level: u8) -> Color {
+        Color(level, level, level)
+    }
+
+    /// Create a new `Color` from normalized red, green, and blue components
+    ///
+    /// Each component is clamped to the range 0.0 - 1.0 and scaled to 0 - 255
+    /// with rounding. NaN components are treated as 0.0.
+    pub fn from_f32(red: f32, green: f32, blue: f32) -> Color {
+        let channel = |value: f32| {
+            if value.is_nan() {
+                0
+            } else {
+                math::roundf(value.max(0.0).min(1.0) * 255.0) as u8
+            }
+        };
+        Color(channel(red), channel(green), channel(blue))
+    }
+
+    /// Get the red, green, and blue components normalized to 0.0 - 1.0
+    pub fn to_f32(&self) -> (f32, f32, f32) {
+        (self.0 as f32 / 255.0, self.1 as f32 / 255.0, self.2 as f32 / 255.0)
+    }
+
+    /// Create a new `Color` from a hex string of the form `#rrggbb`
+    ///
+    /// The leading `#` is optional and the hex digits are case-insensitive.
+    #[cfg(feature = "std")]
+    pub fn from_hex(hex: &str) -> Result<Color> {
+        let digits = if hex.starts_with('#') { &hex[1..] } else { hex };
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_digit(16)) {
+            bail!(ErrorKind::ParseColor(hex.into()));
+        }
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16);
+        Ok(Color(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// Create a new `Color` from a CSS-style color string
+    ///
+    /// Accepts the hex forms understood by `from_hex` as well as
+    /// `rgb(255, 0, 128)` and `rgb(100%, 0%, 50%)`. Out-of-range components
+    /// are clamped to 0 - 255 or 0% - 100%.
+    #[cfg(feature = "std")]
+    pub fn from_css(css: &str) -> Result<Color> {
+        let trimmed = css.trim();
+        let lowercase = trimmed.to_lowercase();
+        if !lowercase.starts_with("rgb(") {
+            return Color::from_hex(trimmed);
+        }
+        if !lowercase.ends_with(')') {
+            bail!(ErrorKind::ParseColor(css.into()));
+        }
+        let components: Vec<&str> = lowercase[4..lowercase.len() - 1]
+            .split(',')
+            .map(|component| component.trim())
+            .collect();
+        if components.len() != 3 {
+            bail!(ErrorKind::ParseColor(css.into()));
+        }
+        let channel = |component: &str| -> Result<u8> {
+            if component.ends_with('%') {
+                let percent = component[..component.len() - 1]
+                    .parse::<f32>()
+                    .chain_err(|| ErrorKind::ParseColor(css.into()))?;
+                if percent.is_nan() {
+                    bail!(ErrorKind::ParseColor(css.into()));
+                }
+                Ok((percent.max(0.0).min(100.0) * 2.55).round() as u8)
+            } else {
+                let value = component.parse::<i64>()
+                    .chain_err(|| ErrorKind::ParseColor(css.into()))?;
+                Ok(cmp::max(0, cmp::min(value, 255)) as u8)
+            }
+        };
+        Ok(Color(channel(components[0])?, channel(components[1])?, channel(components[2])?))
+    }
+
+    /// Format the `Color` as a lowercase hex string of the form `#rrggbb`
+    #[cfg(feature = "std")]
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    /// Pack the `Color` into the low 24 bits of a `u32` as `0xRRGGBB`
+    ///
+    /// The top byte is always zero.
+    pub fn to_u32(&self) -> u32 {
+        (self.0 as u32) << 16 | (self.1 as u32) << 8 | self.2 as u32
+    }
+
+    /// Create a new `Color` from a `u32` packed as `0xRRGGBB`, such as a color
+    /// stored as an integer in a config file
+    ///
+    /// The top byte is ignored.
+    pub fn from_u32(value: u32) -> Color {
+        Color((value >> 16) as u8, (value >> 8) as u8, value as u8)
+    }
+
+    /// Pack the `Color` into 16-bit RGB565, as used by many small displays
+    ///
+    /// Red and blue keep their top 5 bits and green its top 6, so this is
+    /// lossy. Unpacking with `from_rgb565` and packing again gives back the
+    /// same value.
+    pub fn to_rgb565(&self) -> u16 {
+        (self.0 as u16 >> 3) << 11 | (self.1 as u16 >> 2) << 5 | self.2 as u16 >> 3
+    }
+
+    /// Create a new `Color` from a 16-bit RGB565 value
+    ///
+    /// Each channel is widened by repeating its high bits, so full scale maps
+    /// back to 255.
+    pub fn from_rgb565(value: u16) -> Color {
+        Color(widen((value >> 11) as u8, 5),
+              widen((value >> 5) as u8 & 0x3f, 6),
+              widen(value as u8 & 0x1f, 5))
+    }
+
+    /// Pack the `Color` into 8-bit RGB332
+    ///
+    /// Red and green keep their top 3 bits and blue its top 2, so this is
+    /// very lossy. Unpacking with `from_rgb332` and packing again gives back
+    /// the same value.
+    pub fn to_rgb332(&self) -> u8 {
+        (self.0 >> 5) << 5 | (self.1 >> 5) << 2 | self.2 >> 6
+    }
+
+    /// Create a new `Color` from an 8-bit RGB332 value
+    ///
+    /// Each channel is widened by repeating its high bits, so full scale maps
+    /// back to 255.
+    pub fn from_rgb332(value: u8) -> Color {
+        Color(widen(value >> 5, 3), widen(value >> 2 & 0x07, 3), widen(value & 0x03, 2))
+    }
+
+    /// Look up one of the named color constants in this module by name
+    ///
+    /// Names are matched case-insensitively, so `"red"` and `"Red"` both
+    /// return `RED`. Returns `None` if the name is unknown.
+    pub fn from_name(name: &str) -> Option<Color> {
+        NAMED_COLORS.iter()
+            .find(|&&(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, color)| color)
+    }
+
+    /// Find the named color constant closest to this `Color`
+    ///
+    /// Distance is Euclidean in RGB space. Ties go to the constant declared
+    /// first in this module.
+    pub fn nearest_named(&self) -> (&'static str, Color) {
+        *NAMED_COLORS.iter()
+            .min_by_key(|&&(_, ref color)| self.distance_sq(color))
+            .expect("named colors are not empty")
+    }
+
+    /// Euclidean distance to another `Color` in RGB space
+    ///
+    /// Ranges from 0.0 for identical colors up to about 441.7 for `BLACK` and
+    /// `WHITE`.
+    pub fn distance(&self, other: &Color) -> f32 {
+        math::sqrtf(self.distance_sq(other) as f32)
+    }
+
+    /// Squared Euclidean distance to another `Color` in RGB space
+    ///
+    /// Orders colors the same way as `distance` without needing a square root.
+    pub fn distance_sq(&self, other: &Color) -> u32 {
+        let channel = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+        channel(self.0, other.0) + channel(self.1, other.1) + channel(self.2, other.2)
+    }
+
+    /// Create a new `Color` as the count-weighted average of a histogram
+    ///
+    /// Each entry pairs a `Color` with the number of times it occurs, such as
+    /// pixel counts from an image region. Returns `BLACK` if the histogram is
+    /// empty or all counts are zero.
+    pub fn from_histogram(entries: &[(Color, u32)]) -> Color {
+        let (mut red, mut green, mut blue, mut total) = (0u64, 0u64, 0u64, 0u64);
+        for &(color, count) in entries {
+            let count = count as u64;
+            red += color.0 as u64 * count;
+            green += color.1 as u64 * count;
+            blue += color.2 as u64 * count;
+            total += count;
+        }
+
+        if total == 0 {
+            return BLACK;
+        }
+
+        let mean = |sum: u64| ((sum + total / 2) / total) as u8;
+        Color(mean(red), mean(green), mean(blue))
+    }
+
+    /// Create a new `Color` approximating a black body at the given
+    /// temperature in kelvin.
+    ///
+    /// Uses Tanner Helland's curve fit of the black body spectrum. The
+    /// temperature is clamped to 1000-40000 K. Around 6500 K is near white,
+    /// lower temperatures are warmer (more orange), and higher temperatures
+    /// are cooler (more blue).
+    pub fn from_kelvin(kelvin: u16) -> Color {
+        let temp = cmp::max(1000, cmp::min(kelvin, 40000)) as f64 / 100.0;
+        let clamp = |value: f64| math::round(value.max(0.0).min(255.0)) as u8;
+
+        let red = if temp <= 66.0 {
+            255.0
+        } else {
+            329.698727446 * math::pow(temp - 60.0, -0.1332047592)
+        };
+
+        let green = if temp <= 66.0 {
+            99.4708025861 * math::log(temp) - 161.1195681661
+        } else {
+            288.1221695283 * math::pow(temp - 60.0, -0.0755148492)
+        };
+
+        let blue = if temp >= 66.0 {
+            255.0
+        } else if temp <= 19.0 {
+            0.0
+        } else {
+            138.5177312231 * math::log(temp - 10.0) - 305.0447927307
+        };
+
+        Color(clamp(red), clamp(green), clamp(blue))
+    }
+
+    /// Create a new linear `Color` from gamma-encoded channel values
+    ///
+    /// This is the inverse of `gamma_encode`, for reading values back from
+    /// hardware that has been driven with gamma correction. Each channel is
+    /// linearized as `255 * (value / 255) ^ (1 / gamma)`.
+    pub fn from_gamma_encoded(red: u8, green: u8, blue: u8, gamma: f32) -> Color {
+        let channel = |value: u8| gamma_channel(value, 1.0 / gamma);
+        Color(channel(red), channel(green), channel(blue))
+    }
+
+    /// Check whether linear RGB components lie inside the sRGB gamut
+    ///
+    /// Components are on a 0.0 - 1.0 scale, as produced by math in linear or
+    /// OKLab space. Anything outside that range can't be shown on an LED.
+    pub fn is_in_srgb_gamut_from_linear(red: f32, green: f32, blue: f32) -> bool {
+        [red, green, blue].iter().all(|&value| value >= 0.0 && value <= 1.0)
+    }
+
+    /// Create a new `Color` from linear RGB components, mapping them into gamut
+    ///
+    /// Rather than clipping each channel on its own, which shifts the hue, an
+    /// out-of-gamut color is moved toward the gray of equal Rec. 709 luminance
+    /// just far enough for every channel to fit. In-gamut colors are unchanged.
+    pub fn gamut_map(red: f32, green: f32, blue: f32) -> Color {
+        let gray = (0.2126 * red + 0.7152 * green + 0.0722 * blue).max(0.0).min(1.0);
+        let factor = [red, green, blue].iter().fold(1.0f32, |factor, &value| {
+            if value > 1.0 {
+                factor.min((1.0 - gray) / (value - gray))
+            } else if value < 0.0 {
+                factor.min(gray / (gray - value))
+            } else {
+                factor
+            }
+        });
+        let channel = |value: f32| {
+            let mapped = gray + factor * (value - gray);
+            math::roundf(mapped.max(0.0).min(1.0) * 255.0) as u8
+        };
+        Color(channel(red), channel(green), channel(blue))
+    }
+
     /// Create a new `Color` from hue, saturation, and value components.
     ///
     /// Create a `Color` from HSV. Hue is the angle on a circle, with 0 equal
@@ -86,11 +463,12 @@ impl Color {
         let l = lightness as u16;
         let s = saturation as u16;
 
-        let chroma = if saturation < 128 {
-            s.saturating_mul(l) >> 7
-        } else {
-            s.saturating_mul(255 - l) >> 7
-        };
+        // Chroma is largest at half lightness and falls to zero at either end,
+        // C = (1 - |2L - 1|) * S. This must branch on lightness; branching on
+        // saturation lets dark, saturated colors overflow a u8.
+        let span = if lightness < 128 { 2 * l } else { 2 * (255 - l) };
+        let chroma = (s * span + 127) / 255;
+        debug_assert!(chroma <= 255);
 
         let m = l.saturating_sub(chroma >> 1) as u8;
         let c = (chroma as u8).saturating_add(m);
@@ -107,38 +485,195 @@ impl Color {
         }
     }
 
-    // pub fn to_hsl(&self) -> (u8, u8, u8) {
-    //     let red = self.red() as u16;
-    //     let green = self.green() as u16;
-    //     let blue = self.blue() as u16;
+    /// Convert the `Color` to hue, saturation, and value components.
+    ///
+    /// Components use the same scale as `from_hsv`. The conversion uses
+    /// integer math, so round trips are only accurate to within a few steps.
+    pub fn to_hsv(&self) -> (u8, u8, u8) {
+        let red = self.0 as i32;
+        let green = self.1 as i32;
+        let blue = self.2 as i32;
+
+        let cmax = cmp::max(cmp::max(red, green), blue);
+        let cmin = cmp::min(cmp::min(red, green), blue);
+        let delta = cmax - cmin;
+
+        if delta == 0 {
+            // color is greyscale
+            return (0, 0, cmax as u8);
+        }
+
+        let saturation = 255 * delta / cmax;
+        let hue = if cmax == red {
+            43 * (green - blue) / delta
+        } else if cmax == green {
+            86 + 43 * (blue - red) / delta
+        } else {
+            172 + 43 * (red - green) / delta
+        };
+
+        // negative hues wrap around the circle
+        ((hue + 256) as u8, saturation as u8, cmax as u8)
+    }
+
+    /// Convert the `Color` to hue, saturation, and lightness components.
+    ///
+    /// Components use the same scale as `from_hsl`, and the hue matches
+    /// `to_hsv`. Integer rounding means a round trip through `from_hsl` can be
+    /// off by a step or two.
+    pub fn to_hsl(&self) -> (u8, u8, u8) {
+        let cmax = cmp::max(cmp::max(self.0, self.1), self.2) as u32;
+        let cmin = cmp::min(cmp::min(self.0, self.1), self.2) as u32;
+        let delta = cmax - cmin;
+        let lightness = (cmax + cmin) / 2;
+
+        if delta == 0 {
+            // color is greyscale
+            return (0, 0, lightness as u8);
+        }
+
+        // Invert the chroma span used by `from_hsl`, working from the sum
+        // rather than the halved lightness to keep the lost bit. The span is
+        // never smaller than the chroma, so saturation stays within 255.
+        let sum = cmax + cmin;
+        let span = if sum < 255 { sum } else { 510 - sum };
+        let saturation = (delta * 255 + span / 2) / span;
+        (self.to_hsv().0, saturation as u8, lightness as u8)
+    }
+
+    /// Linearly interpolate between this `Color` and `other`
+    ///
+    /// `t` is clamped to the range `0.0..=1.0`, where `0.0` returns this
+    /// `Color` and `1.0` returns `other`. Each channel is rounded to the
+    /// nearest integer.
+    pub fn lerp(&self, other: &Color, t: f32) -> Color {
+        let t = t.max(0.0).min(1.0);
+        let channel = |from: u8, to: u8| {
+            math::roundf(from as f32 + (to as f32 - from as f32) * t) as u8
+        };
+        Color(channel(self.0, other.0),
+              channel(self.1, other.1),
+              channel(self.2, other.2))
+    }
 
-    //     let cmax = cmp::max(cmp::max(red, green), blue);
-    //     let cmin = cmp::min(cmp::min(red, green), blue);
-    //     let delta = cmax - cmin;
+    /// Composite this `Color` over a `background` with the given opacity
+    ///
+    /// Standard source-over compositing, per channel: an `alpha` of 0 gives
+    /// `background` and 255 gives this `Color`. Uses integer math, rounding to
+    /// the nearest value.
+    pub fn over(&self, background: &Color, alpha: u8) -> Color {
+        let alpha = alpha as u32;
+        let channel = |fg: u8, bg: u8| {
+            ((fg as u32 * alpha + bg as u32 * (255 - alpha) + 127) / 255) as u8
+        };
+        Color(channel(self.0, background.0),
+              channel(self.1, background.1),
+              channel(self.2, background.2))
+    }
 
-    //     let hue = if delta == 0 {
-    //         0
-    //     } else if cmax == red {
-    //         43 * (green - blue) / delta
-    //         43 * (((self.green() - self.blue()) / delta) % 6)
-    //     } else if cmax == self.green() {
-    //         43 * (((self.blue() - self.red()) / delta ) + 2)
-    //     } else {
-    //         43 * (((self.red() - self.green()) / delta) + 4)
-    //     };
+    /// Composite `other` on top of this `Color` using a blend mode
+    ///
+    /// This `Color` is the base layer. Each channel is computed separately
+    /// with integer math, rounding to the nearest value.
+    pub fn blend_mode(&self, other: &Color, mode: BlendMode) -> Color {
+        let multiply = |a: u16, b: u16| (a * b + 127) / 255;
+        let screen = |a: u16, b: u16| 255 - multiply(255 - a, 255 - b);
+        let channel = |base: u8, top: u8| {
+            let (base, top) = (base as u16, top as u16);
+            let value = match mode {
+                BlendMode::Normal => top,
+                BlendMode::Multiply => multiply(base, top),
+                BlendMode::Screen => screen(base, top),
+                BlendMode::Overlay if base < 128 => multiply(2 * base, top),
+                BlendMode::Overlay => screen(2 * base - 255, top),
+                BlendMode::Add => cmp::min(base + top, 255),
+            };
+            value as u8
+        };
+        Color(channel(self.0, other.0),
+              channel(self.1, other.1),
+              channel(self.2, other.2))
+    }
 
-    //     let lightness = (cmax + cmin) >> 1;
+    /// Invert each channel, giving the complementary color
+    pub fn invert(&self) -> Color {
+        Color(255 - self.0, 255 - self.1, 255 - self.2)
+    }
 
-    //     let saturation = if delta == 0 {
-    //         0
-    //     } else if lightness < 128 {
-    //         delta / (255 - (lightness << 1))
-    //     } else {
-    //         delta / ((lightness << 1) - 255)
-    //     };
+    /// Scale the intensity of each channel by `factor`
+    ///
+    /// Negative factors are treated as `0.0`, which yields `BLACK`. Factors
+    /// above `1.0` brighten the color, saturating each channel at 255. Unlike
+    /// changing HSL lightness, this preserves the hue of simple dimming.
+    pub fn scale(&self, factor: f32) -> Color {
+        let factor = factor.max(0.0);
+        let channel = |value: u8| math::roundf(value as f32 * factor).min(255.0) as u8;
+        Color(channel(self.0), channel(self.1), channel(self.2))
+    }
+
+    /// Scale the intensity of each channel by `factor / 255`
+    ///
+    /// An integer-only alternative to `scale` for targets without floating
+    /// point, where a `factor` of 255 is the identity and 0 yields `BLACK`.
+    pub fn scale_u8(&self, factor: u8) -> Color {
+        let channel = |value: u8| (value as u16 * factor as u16 / 255) as u8;
+        Color(channel(self.0), channel(self.1), channel(self.2))
+    }
 
-    //     (hue, saturation, lightness)
-    // }
+    /// Apply gamma correction to the `Color` for output to hardware
+    ///
+    /// Each channel is encoded as `255 * (value / 255) ^ gamma`, so that
+    /// perceived brightness is closer to linear. A typical `gamma` for LEDs is
+    /// around 2.2.
+    pub fn gamma_encode(&self, gamma: f32) -> Color {
+        let channel = |value: u8| gamma_channel(value, gamma);
+        Color(channel(self.0), channel(self.1), channel(self.2))
+    }
+
+    /// Perceived brightness of the `Color`
+    ///
+    /// Computed with the Rec. 709 luma weights, which is useful for driving a
+    /// single-color LED from an RGB source.
+    pub fn luminance(&self) -> u8 {
+        let luma = 0.2126 * self.0 as f32 + 0.7152 * self.1 as f32 + 0.0722 * self.2 as f32;
+        math::roundf(luma).min(255.0) as u8
+    }
+
+    /// Reduce the `Color` to a single brightness value using the given model
+    pub fn to_mono(&self, weighting: MonoWeighting) -> u8 {
+        let (red, green, blue) = (self.0 as u32, self.1 as u32, self.2 as u32);
+        match weighting {
+            MonoWeighting::Rec601 => {
+                let luma = 0.299 * red as f32 + 0.587 * green as f32 + 0.114 * blue as f32;
+                math::roundf(luma).min(255.0) as u8
+            }
+            MonoWeighting::Rec709 => self.luminance(),
+            MonoWeighting::Average => ((red + green + blue + 1) / 3) as u8,
+            MonoWeighting::Max => cmp::max(cmp::max(self.0, self.1), self.2),
+        }
+    }
+
+    /// Render the `Color` as a swatch for a truecolor terminal
+    ///
+    /// Returns two spaces with this `Color` as the background, followed by an
+    /// escape sequence that resets the terminal colors.
+    #[cfg(feature = "std")]
+    pub fn truecolor_swatch(&self) -> String {
+        format!("\x1b[48;2;{};{};{}m  \x1b[0m", self.0, self.1, self.2)
+    }
+
+    /// Describe the `Color` for a truecolor terminal
+    ///
+    /// Gives the name of the color if it is one of the named constants in
+    /// this module, or its hex form otherwise, followed by a swatch from
+    /// `truecolor_swatch`. Handy for debug output.
+    #[cfg(feature = "std")]
+    pub fn ansi_preview(&self) -> String {
+        let label = NAMED_COLORS.iter()
+            .find(|&&(_, color)| color == *self)
+            .map_or_else(|| self.to_hex(), |&(name, _)| name.to_string());
+        format!("{} {}", label, self.truecolor_swatch())
+    }
 
     pub fn red(&self) -> u8 {
         self.0
@@ -151,12 +686,482 @@ impl Color {
     pub fn blue(&self) -> u8 {
         self.2
     }
+
+    /// Copy of this `Color` with the red channel replaced
+    pub fn with_red(self, red: u8) -> Color {
+        Color(red, self.1, self.2)
+    }
+
+    /// Copy of this `Color` with the green channel replaced
+    pub fn with_green(self, green: u8) -> Color {
+        Color(self.0, green, self.2)
+    }
+
+    /// Copy of this `Color` with the blue channel replaced
+    pub fn with_blue(self, blue: u8) -> Color {
+        Color(self.0, self.1, blue)
+    }
+}
+
+/// Additive color mixing, saturating each channel at 255
+impl Add for Color {
+    type Output = Color;
+
+    fn add(self, other: Color) -> Color {
+        Color(self.0.saturating_add(other.0),
+              self.1.saturating_add(other.1),
+              self.2.saturating_add(other.2))
+    }
+}
+
+/// Per-channel subtraction, saturating each channel at 0
+impl Sub for Color {
+    type Output = Color;
+
+    fn sub(self, other: Color) -> Color {
+        Color(self.0.saturating_sub(other.0),
+              self.1.saturating_sub(other.1),
+              self.2.saturating_sub(other.2))
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((red, green, blue): (u8, u8, u8)) -> Color {
+        Color(red, green, blue)
+    }
+}
+
+impl From<[u8; 3]> for Color {
+    fn from([red, green, blue]: [u8; 3]) -> Color {
+        Color(red, green, blue)
+    }
+}
+
+impl From<Color> for (u8, u8, u8) {
+    fn from(color: Color) -> (u8, u8, u8) {
+        (color.0, color.1, color.2)
+    }
+}
+
+impl From<Color> for [u8; 3] {
+    fn from(color: Color) -> [u8; 3] {
+        [color.0, color.1, color.2]
+    }
+}
+
+// Raise a normalized channel value to the given exponent
+pub(crate) fn gamma_channel(value: u8, exponent: f32) -> u8 {
+    math::roundf(255.0 * math::powf(value as f32 / 255.0, exponent)).max(0.0).min(255.0) as u8
+}
+
+// Widen a channel value of the given bit width to 8 bits by repeating its bits
+fn widen(value: u8, bits: u32) -> u8 {
+    let mut result = value << (8 - bits);
+    let mut filled = bits;
+    while filled < 8 {
+        result |= result >> filled;
+        filled *= 2;
+    }
+    result
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+impl ::serde::Serialize for Color {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where S: ::serde::Serializer
+    {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+#[cfg(all(feature = "serde", feature = "std"))]
+impl<'de> ::serde::Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Color, D::Error>
+        where D: ::serde::Deserializer<'de>
+    {
+        use serde::de::Error;
+        let hex = String::deserialize(deserializer)?;
+        Color::from_hex(&hex).map_err(|e| D::Error::custom(e.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_status() {
+        assert_eq!(GREEN, status(Status::Ok));
+        assert_eq!(Color(255, 191, 0), status(Status::Warn));
+        assert_eq!(RED, status(Status::Error));
+        assert_eq!(BLUE, status(Status::Info));
+    }
+
+    #[test]
+    fn test_dither() {
+        for &level in &[0.0, 0.1, 0.25, 0.3, 0.5, 0.9, 1.0] {
+            let dither = Dither::new(level);
+            let on = (0..1000).filter(|&frame| dither.is_on(frame)).count();
+            assert!((on as f32 - level * 1000.0).abs() <= 1.0,
+                    "level {} was on for {} of 1000 frames", level, on);
+        }
+        // On frames are spread out rather than bunched together
+        let half = Dither::new(0.5);
+        assert!((0..100).all(|frame| half.is_on(frame) != half.is_on(frame + 1)));
+        assert_eq!(1.0, Dither::new(1.5).level());
+        assert_eq!(0.0, Dither::new(-0.5).level());
+        assert_eq!(0.0, Dither::new(::std::f32::NAN).level());
+    }
+
+    #[test]
+    fn test_hue_wheel() {
+        use std::collections::HashSet;
+
+        let wheel: Vec<Color> = hue_wheel(255, 255).collect();
+        assert_eq!(256, wheel.len());
+        assert_eq!(RED, wheel[0]);
+        assert_eq!(Color::from_hsv(128, 255, 255), wheel[128]);
+        let distinct: HashSet<&Color> = wheel.iter().collect();
+        assert!(distinct.len() > 200);
+        assert!(hue_wheel(0, 100).all(|color| color == Color(100, 100, 100)));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let color: Color = (10, 20, 30).into();
+        assert_eq!(Color(10, 20, 30), color);
+        let color: Color = [10, 20, 30].into();
+        assert_eq!(Color(10, 20, 30), color);
+        let tuple: (u8, u8, u8) = Color(10, 20, 30).into();
+        assert_eq!((10, 20, 30), tuple);
+        let array: [u8; 3] = Color(10, 20, 30).into();
+        assert_eq!([10, 20, 30], array);
+    }
+
+    #[test]
+    fn test_add_sub() {
+        assert_eq!(YELLOW, RED + GREEN);
+        assert_eq!(MAGENTA, RED + BLUE);
+        assert_eq!(WHITE, WHITE + Color(12, 34, 56));
+        assert_eq!(Color(255, 200, 150), Color(200, 100, 50) + Color(100, 100, 100));
+        assert_eq!(RED, MAGENTA - BLUE);
+        assert_eq!(BLACK, BLACK - Color(12, 34, 56));
+        assert_eq!(Color(100, 0, 0), Color(200, 100, 50) - Color(100, 100, 100));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hex() {
+        assert_eq!(Color(0x12, 0xab, 0xff), Color::from_hex("#12abff").unwrap());
+        assert_eq!(Color(0x12, 0xab, 0xff), Color::from_hex("12ABFF").unwrap());
+        assert_eq!("#12abff", Color(0x12, 0xab, 0xff).to_hex());
+        assert!(Color::from_hex("#12abf").is_err());
+        assert!(Color::from_hex("#12abfg").is_err());
+        assert!(Color::from_hex("+12abff").is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_css() {
+        assert_eq!(Color(255, 0, 128), Color::from_css("rgb(255, 0, 128)").unwrap());
+        assert_eq!(Color(255, 0, 128), Color::from_css(" RGB(255,0,128) ").unwrap());
+        assert_eq!(Color(255, 0, 128), Color::from_css("rgb(100%, 0%, 50%)").unwrap());
+        assert_eq!(Color(255, 0, 3), Color::from_css("rgb(300, -5, 1%)").unwrap());
+        assert_eq!(Color(255, 0, 0), Color::from_css("rgb(150%, -10%, 0)").unwrap());
+        assert_eq!(Color(0x12, 0xab, 0xff), Color::from_css("#12abff").unwrap());
+        for bad in &["rgb(1, 2)", "rgb(1, 2, 3, 4)", "rgb(1, 2, x)", "rgb(1, 2, 3", "rgb(a%, 0, 0)",
+                     "hsl(1, 2, 3)"] {
+            match Color::from_css(bad) {
+                Err(Error(ErrorKind::ParseColor(_), _)) => {}
+                other => panic!("expected ParseColor for {:?}, got {:?}", bad, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_f32() {
+        assert_eq!(Color(255, 0, 128), Color::from_f32(1.0, 0.0, 0.5));
+        assert_eq!(Color(255, 0, 0), Color::from_f32(1.5, -0.5, 0.0));
+        assert_eq!(Color(1, 0, 254), Color::from_f32(0.5 / 255.0, 0.49 / 255.0, 0.997));
+        assert_eq!(Color(0, 0, 255), Color::from_f32(::std::f32::NAN, 0.0, 1.0));
+        assert_eq!((1.0, 0.0, 0.2), Color(255, 0, 51).to_f32());
+        assert_eq!(Color(12, 34, 56), {
+            let (red, green, blue) = Color(12, 34, 56).to_f32();
+            Color::from_f32(red, green, blue)
+        });
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Some(RED), Color::from_name("red"));
+        assert_eq!(Some(MAGENTA), Color::from_name("MaGenta"));
+        assert_eq!(Some(BLACK), Color::from_name("BLACK"));
+        assert_eq!(None, Color::from_name("chartreuse"));
+    }
+
+    #[test]
+    fn test_nearest_named() {
+        assert_eq!(("red", RED), Color(240, 20, 10).nearest_named());
+        assert_eq!(("cyan", CYAN), CYAN.nearest_named());
+        assert_eq!(("black", BLACK), Color(100, 100, 100).nearest_named());
+        assert_eq!(("white", WHITE), Color(160, 160, 160).nearest_named());
+        assert_eq!(("yellow", YELLOW), status(Status::Warn).nearest_named());
+    }
+
+    #[test]
+    fn test_from_histogram() {
+        assert_eq!(BLACK, Color::from_histogram(&[]));
+        assert_eq!(BLACK, Color::from_histogram(&[(RED, 0)]));
+        assert_eq!(RED, Color::from_histogram(&[(RED, 7)]));
+        assert_eq!(Color(128, 0, 128), Color::from_histogram(&[(RED, 1), (BLUE, 1)]));
+
+        let reddish = Color::from_histogram(&[(RED, 90), (BLUE, 10)]);
+        assert_eq!(Color(230, 0, 26), reddish);
+        assert!(reddish.red() > reddish.blue());
+    }
+
+    #[test]
+    fn test_blend_mode() {
+        let color = Color(12, 128, 250);
+        assert_eq!(color, WHITE.blend_mode(&color, BlendMode::Multiply));
+        assert_eq!(color, BLACK.blend_mode(&color, BlendMode::Screen));
+        assert_eq!(color, BLACK.blend_mode(&color, BlendMode::Normal));
+        assert_eq!(BLACK, BLACK.blend_mode(&color, BlendMode::Multiply));
+        assert_eq!(WHITE, WHITE.blend_mode(&color, BlendMode::Screen));
+        assert_eq!(Color(64, 64, 64),
+                   Color(128, 128, 128).blend_mode(&Color(128, 128, 128), BlendMode::Multiply));
+        assert_eq!(Color(192, 192, 192),
+                   Color(128, 128, 128).blend_mode(&Color(128, 128, 128), BlendMode::Screen));
+        assert_eq!(Color(0, 128, 255),
+                   Color(0, 128, 255).blend_mode(&Color(128, 128, 128), BlendMode::Overlay));
+        assert_eq!(Color(255, 200, 150),
+                   Color(200, 100, 50).blend_mode(&Color(100, 100, 100), BlendMode::Add));
+    }
+
+    #[test]
+    fn test_invert() {
+        assert_eq!(BLACK, WHITE.invert());
+        assert_eq!(WHITE, BLACK.invert());
+        assert_eq!(CYAN, RED.invert());
+        assert_eq!(Color(243, 127, 5), Color(12, 128, 250).invert());
+    }
+
+    #[test]
+    fn test_lerp() {
+        assert_eq!(RED, RED.lerp(&BLUE, 0.0));
+        assert_eq!(BLUE, RED.lerp(&BLUE, 1.0));
+        assert_eq!(Color(128, 0, 128), RED.lerp(&BLUE, 0.5));
+        assert_eq!(Color(64, 64, 64), BLACK.lerp(&WHITE, 0.25));
+        assert_eq!(RED, RED.lerp(&BLUE, -1.0));
+        assert_eq!(BLUE, RED.lerp(&BLUE, 2.0));
+    }
+
+    #[test]
+    fn test_from_kelvin() {
+        let daylight = Color::from_kelvin(6500);
+        assert_eq!(255, daylight.red());
+        assert!(daylight.green() >= 250);
+        assert!(daylight.blue() >= 245);
+
+        let warm = Color::from_kelvin(2000);
+        assert!(warm.red() > warm.green());
+        assert!(warm.green() > warm.blue());
+
+        assert_eq!(Color::from_kelvin(1000), Color::from_kelvin(0));
+        assert_eq!(Color::from_kelvin(40000), Color::from_kelvin(65535));
+        assert!(Color::from_kelvin(40000).blue() > Color::from_kelvin(40000).red());
+    }
+
+    #[test]
+    fn test_scale() {
+        assert_eq!(Color(100, 50, 25), Color(200, 100, 50).scale(0.5));
+        assert_eq!(BLACK, Color(200, 100, 50).scale(0.0));
+        assert_eq!(BLACK, Color(200, 100, 50).scale(-1.0));
+        assert_eq!(Color(200, 100, 50), Color(200, 100, 50).scale(1.0));
+        assert_eq!(Color(255, 200, 100), Color(200, 100, 50).scale(2.0));
+        assert_eq!(WHITE, WHITE.scale(10.0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_truecolor_swatch() {
+        assert_eq!("\x1b[48;2;18;171;255m  \x1b[0m",
+                   Color(18, 171, 255).truecolor_swatch());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_ansi_preview() {
+        let preview = Color(18, 171, 255).ansi_preview();
+        assert!(preview.starts_with("#12abff "));
+        assert!(preview.contains("\x1b[48;2;18;171;255m"));
+        assert!(preview.ends_with("\x1b[0m"));
+        assert_eq!(format!("red {}", RED.truecolor_swatch()), RED.ansi_preview());
+    }
+
+    #[test]
+    fn test_scale_u8() {
+        assert_eq!(Color(200, 100, 50), Color(200, 100, 50).scale_u8(255));
+        assert_eq!(Color(128, 0, 64), Color(255, 0, 128).scale_u8(128));
+        assert_eq!(BLACK, Color(200, 100, 50).scale_u8(0));
+    }
+
+    #[test]
+    fn test_to_mono() {
+        assert_eq!(150, GREEN.to_mono(MonoWeighting::Rec601));
+        assert_eq!(182, GREEN.to_mono(MonoWeighting::Rec709));
+        assert_eq!(85, GREEN.to_mono(MonoWeighting::Average));
+        assert_eq!(255, GREEN.to_mono(MonoWeighting::Max));
+        for &weighting in &[MonoWeighting::Rec601,
+                            MonoWeighting::Rec709,
+                            MonoWeighting::Average,
+                            MonoWeighting::Max] {
+            assert_eq!(0, BLACK.to_mono(weighting));
+            assert_eq!(255, WHITE.to_mono(weighting));
+        }
+    }
+
+    #[test]
+    fn test_gamma() {
+        assert_eq!(Color(56, 56, 56), Color(128, 128, 128).gamma_encode(2.2));
+        assert_eq!(Color(128, 128, 128), Color::from_gamma_encoded(56, 56, 56, 2.2));
+        assert_eq!(BLACK, Color::from_gamma_encoded(0, 0, 0, 2.2));
+        assert_eq!(WHITE, Color::from_gamma_encoded(255, 255, 255, 2.2));
+
+        let color = Color(200, 100, 50);
+        let encoded = color.gamma_encode(2.2);
+        let decoded = Color::from_gamma_encoded(encoded.red(),
+                                                encoded.green(),
+                                                encoded.blue(),
+                                                2.2);
+        assert!((decoded.red() as i32 - 200).abs() <= 1);
+        assert!((decoded.green() as i32 - 100).abs() <= 1);
+        assert!((decoded.blue() as i32 - 50).abs() <= 2);
+    }
+
+    #[test]
+    fn test_gamut() {
+        assert!(Color::is_in_srgb_gamut_from_linear(0.5, 0.2, 0.9));
+        assert!(Color::is_in_srgb_gamut_from_linear(0.0, 1.0, 0.0));
+        assert_eq!(Color(128, 51, 230), Color::gamut_map(0.5, 0.2, 0.9));
+
+        assert!(!Color::is_in_srgb_gamut_from_linear(1.5, 0.5, 0.5));
+        assert!(!Color::is_in_srgb_gamut_from_linear(0.5, -0.1, 0.5));
+        // Desaturated toward gray rather than clipped to (255, 128, 128)
+        let mapped = Color::gamut_map(1.5, 0.5, 0.5);
+        assert_eq!(255, mapped.red());
+        assert_eq!(mapped.green(), mapped.blue());
+        assert!(mapped.green() > 128);
+        assert_eq!(BLACK, Color::gamut_map(-0.5, -0.5, -0.5));
+        assert_eq!(WHITE, Color::gamut_map(2.0, 2.0, 2.0));
+    }
+
+    #[test]
+    fn test_luminance() {
+        assert_eq!(255, WHITE.luminance());
+        assert_eq!(0, BLACK.luminance());
+        assert_eq!(54, RED.luminance());
+        assert_eq!(182, GREEN.luminance());
+        assert_eq!(18, BLUE.luminance());
+        assert!(GREEN.luminance() > RED.luminance());
+        assert!(RED.luminance() > BLUE.luminance());
+    }
+
+    #[test]
+    fn test_u32() {
+        assert_eq!(0x123456, Color::from_rgb(0x12, 0x34, 0x56).to_u32());
+        assert_eq!(Color::from_rgb(0x12, 0x34, 0x56), Color::from_u32(0x123456));
+        assert_eq!(0x123456, Color::from_u32(0x123456).to_u32());
+        assert_eq!(0xff0000, RED.to_u32());
+        assert_eq!(0x0000ff, BLUE.to_u32());
+        assert_eq!(Color::from_rgb(0xff, 0x88, 0x00), Color::from_u32(0xabff8800));
+        assert_eq!(0xffffff, Color::from_u32(0xffffffff).to_u32());
+    }
+
+    #[test]
+    fn test_rgb565() {
+        assert_eq!(0xf800, RED.to_rgb565());
+        assert_eq!(0x07e0, Color::from_rgb(0, 255, 0).to_rgb565());
+        assert_eq!(0x001f, BLUE.to_rgb565());
+        assert_eq!(WHITE, Color::from_rgb565(0xffff));
+        assert_eq!(BLACK, Color::from_rgb565(0));
+        for value in 0..0x10000 {
+            assert_eq!(value as u16, Color::from_rgb565(value as u16).to_rgb565());
+        }
+        for &color in &[Color::from_rgb(12, 34, 56), Color::from_rgb(200, 100, 250)] {
+            let packed = color.to_rgb565();
+            let unpacked = Color::from_rgb565(packed);
+            assert_eq!(packed, unpacked.to_rgb565());
+            assert!((color.0 as i32 - unpacked.0 as i32).abs() < 8);
+            assert!((color.1 as i32 - unpacked.1 as i32).abs() < 4);
+            assert!((color.2 as i32 - unpacked.2 as i32).abs() < 8);
+        }
+    }
+
+    #[test]
+    fn test_rgb332() {
+        assert_eq!(0xe0, RED.to_rgb332());
+        assert_eq!(0x03, BLUE.to_rgb332());
+        assert_eq!(WHITE, Color::from_rgb332(0xff));
+        assert_eq!(BLACK, Color::from_rgb332(0));
+        for value in 0..256 {
+            assert_eq!(value as u8, Color::from_rgb332(value as u8).to_rgb332());
+        }
+        let color = Color::from_rgb(12, 34, 56);
+        let unpacked = Color::from_rgb332(color.to_rgb332());
+        assert!((color.0 as i32 - unpacked.0 as i32).abs() < 32);
+        assert!((color.1 as i32 - unpacked.1 as i32).abs() < 32);
+        assert!((color.2 as i32 - unpacked.2 as i32).abs() < 64);
+    }
+
+    #[test]
+    fn test_over() {
+        let foreground = Color::from_rgb(255, 0, 0);
+        let background = Color::from_rgb(0, 0, 255);
+        assert_eq!(background, foreground.over(&background, 0));
+        assert_eq!(foreground, foreground.over(&background, 255));
+        assert_eq!(Color::from_rgb(128, 0, 127), foreground.over(&background, 128));
+        assert_eq!(Color::from_rgb(128, 128, 128), WHITE.over(&BLACK, 128));
+        assert_eq!(Color::from_rgb(10, 20, 30), Color::from_rgb(10, 20, 30).over(&WHITE, 255));
+    }
+
+    #[test]
+    fn test_distance() {
+        let color = Color::from_rgb(12, 34, 56);
+        assert_eq!(0, color.distance_sq(&color));
+        assert_eq!(0.0, color.distance(&color));
+        assert_eq!(3 * 255 * 255, BLACK.distance_sq(&WHITE));
+        assert!((BLACK.distance(&WHITE) - 441.673).abs() < 0.001);
+        assert_eq!(WHITE.distance_sq(&BLACK), BLACK.distance_sq(&WHITE));
+        assert_eq!(5.0, Color::from_rgb(0, 3, 4).distance(&BLACK));
+    }
+
+    #[test]
+    fn test_gray() {
+        assert_eq!(Color(128, 128, 128), Color::gray(128));
+        assert_eq!(BLACK, Color::gray(0));
+        assert_eq!(WHITE, Color::gray(255));
+        assert_eq!(77, Color::gray(77).luminance());
+    }
+
+    #[test]
+    fn test_with_channel() {
+        let color = Color::from_rgb(10, 20, 30);
+        assert_eq!(Color::from_rgb(99, 20, 30), color.with_red(99));
+        assert_eq!(Color::from_rgb(10, 99, 30), color.with_green(99));
+        assert_eq!(Color::from_rgb(10, 20, 99), color.with_blue(99));
+        assert_eq!(color, color.with_red(10).with_green(20).with_blue(30));
+    }
+
+    #[cfg(all(feature = "serde", feature = "std"))]
+    #[test]
+    fn test_serde_round_trip() {
+        let json = serde_json::to_string(&MAGENTA).unwrap();
+        assert_eq!("\"#ff00ff\"", json);
+        assert_eq!(MAGENTA, serde_json::from_str::<Color>(&json).unwrap());
+        assert!(serde_json::from_str::<Color>("\"ff00f\"").is_err());
+    }
+
     #[test]
     fn test_hsv_to_rgb() {
         assert_eq!(Color(0, 0, 0), Color::from_hsv(0, 0, 0));
@@ -177,6 +1182,24 @@ mod tests {
         assert_eq!(Color(0, 0, 128), Color::from_hsv(172, 255, 128));
     }
 
+    #[test]
+    fn test_rgb_to_hsv() {
+        assert_eq!((0, 0, 0), BLACK.to_hsv());
+        assert_eq!((0, 0, 255), WHITE.to_hsv());
+        assert_eq!((0, 0, 128), Color(128, 128, 128).to_hsv());
+        assert_eq!((0, 255, 255), RED.to_hsv());
+        assert_eq!((86, 255, 255), GREEN.to_hsv());
+        assert_eq!((172, 255, 255), BLUE.to_hsv());
+        assert_eq!((43, 255, 255), YELLOW.to_hsv());
+        assert_eq!((213, 255, 255), MAGENTA.to_hsv());
+        for &(h, s, v) in &[(21, 255, 255), (64, 192, 200), (150, 128, 128), (240, 255, 96)] {
+            let (hue, saturation, value) = Color::from_hsv(h, s, v).to_hsv();
+            assert!((hue as i32 - h as i32).abs() <= 2, "hue {} vs {}", hue, h);
+            assert!((saturation as i32 - s as i32).abs() <= 2, "saturation {} vs {}", saturation, s);
+            assert!((value as i32 - v as i32).abs() <= 2, "value {} vs {}", value, v);
+        }
+    }
+
     #[test]
     fn test_hsl_to_rgb() {
         assert_eq!(Color(  0,   0,   0), Color::from_hsl(  0,   0,   0));
@@ -184,16 +1207,49 @@ mod tests {
         assert_eq!(Color(255, 255, 255), Color::from_hsl(255,   0, 255));
         assert_eq!(Color(255, 255, 255), Color::from_hsl(255, 255, 255));
         assert_eq!(Color(127, 127, 127), Color::from_hsl(  0,   0, 127));
-        assert_eq!(Color(255,   0,   0), Color::from_hsl(  0, 255, 127));
-        assert_eq!(Color(255, 125,   0), Color::from_hsl( 21, 255, 127));
-        assert_eq!(Color(254, 255,   0), Color::from_hsl( 43, 255, 127));
-        assert_eq!(Color(128, 255,   0), Color::from_hsl( 64, 255, 127));
-        assert_eq!(Color(  0, 255, 251), Color::from_hsl(128, 255, 127));
-        assert_eq!(Color(125,   0, 255), Color::from_hsl(193, 255, 127));
-        assert_eq!(Color(190, 126,  64), Color::from_hsl( 21, 127, 127));
-        assert_eq!(Color(189, 190,  64), Color::from_hsl( 43, 127, 127));
-        assert_eq!(Color(127, 190,  64), Color::from_hsl( 64, 127, 127));
-        assert_eq!(Color( 64, 190, 188), Color::from_hsl(128, 127, 127));
-        assert_eq!(Color(126,  64, 190), Color::from_hsl(193, 127, 127));
+        assert_eq!(Color(254,   0,   0), Color::from_hsl(  0, 255, 127));
+        assert_eq!(Color(254, 125,   0), Color::from_hsl( 21, 255, 127));
+        assert_eq!(Color(253, 254,   0), Color::from_hsl( 43, 255, 127));
+        assert_eq!(Color(127, 254,   0), Color::from_hsl( 64, 255, 127));
+        assert_eq!(Color(  0, 254, 250), Color::from_hsl(128, 255, 127));
+        assert_eq!(Color(125,   0, 254), Color::from_hsl(193, 255, 127));
+        assert_eq!(Color(191, 126,  64), Color::from_hsl( 21, 127, 127));
+        assert_eq!(Color(190, 191,  64), Color::from_hsl( 43, 127, 127));
+        assert_eq!(Color(127, 191,  64), Color::from_hsl( 64, 127, 127));
+        assert_eq!(Color( 64, 191, 189), Color::from_hsl(128, 127, 127));
+        assert_eq!(Color(126,  64, 191), Color::from_hsl(193, 127, 127));
+    }
+
+    #[test]
+    fn test_rgb_to_hsl() {
+        assert_eq!((0, 0, 0), BLACK.to_hsl());
+        assert_eq!((0, 0, 255), WHITE.to_hsl());
+        assert_eq!((0, 0, 128), Color(128, 128, 128).to_hsl());
+        assert_eq!((0, 255, 127), RED.to_hsl());
+        assert_eq!((172, 255, 127), BLUE.to_hsl());
+        assert_eq!((0, 255, 0), Color(1, 0, 0).to_hsl());
+        assert_eq!((0, 255, 254), Color(255, 254, 254).to_hsl());
+        for &(h, s, l) in &[(21, 255, 127), (64, 192, 200), (150, 128, 128), (240, 255, 96)] {
+            let (hue, saturation, lightness) = Color::from_hsl(h, s, l).to_hsl();
+            assert!((hue as i32 - h as i32).abs() <= 2, "hue {} vs {}", hue, h);
+            assert!((saturation as i32 - s as i32).abs() <= 3, "saturation {} vs {}", saturation, s);
+            assert!((lightness as i32 - l as i32).abs() <= 2, "lightness {} vs {}", lightness, l);
+        }
+    }
+
+    #[test]
+    fn test_hsl_boundaries() {
+        // Either side of the saturation midpoint behaves the same way
+        assert_eq!(Color(192,  65,  65), Color::from_hsl(  0, 128, 128));
+        assert_eq!(Color(192,  65,  65), Color::from_hsl(  0, 127, 128));
+        assert_eq!(Color( 96,  32,  32), Color::from_hsl(  0, 128,  64));
+        assert_eq!(Color( 96,  32,  32), Color::from_hsl(  0, 127,  64));
+        assert_eq!(Color(224, 161, 161), Color::from_hsl(  0, 128, 192));
+        assert_eq!(Color(224, 161, 161), Color::from_hsl(  0, 127, 192));
+        // Full lightness is white and zero lightness is black at any saturation
+        for saturation in 0..=255u8 {
+            assert_eq!(WHITE, Color::from_hsl(85, saturation, 255));
+            assert_eq!(BLACK, Color::from_hsl(85, saturation, 0));
+        }
     }
 }
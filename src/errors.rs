@@ -4,6 +4,51 @@ error_chain! {
             description("invalid device path")
             display("invalid device path: '{}'", path)
         }
+        MissingAttribute(path: String, attribute: String) {
+            description("LED device attribute missing")
+            display("LED device attribute missing: '{}' in '{}'", attribute, path)
+        }
+        InvalidAttributeName(name: String) {
+            description("invalid sysfs attribute name")
+            display("invalid sysfs attribute name: '{}'", name)
+        }
+        InvalidFileContents(file: String, contents: String) {
+            description("invalid sysfs file contents")
+            display("invalid contents in '{}': '{}'", file, contents)
+        }
+        PermissionDenied(path: String) {
+            description("permission denied")
+            display("permission denied: '{}' (try running as root or adding a udev rule)", path)
+        }
+        ReadOnlyDevice(path: String) {
+            description("LED brightness is read-only")
+            display("LED brightness is read-only: '{}'", path)
+        }
+        WriteFailed(path: String, value: String) {
+            description("failed to write sysfs file")
+            display("failed to write '{}' to '{}'", value, path)
+        }
+        MissingChannel(channel: String) {
+            description("RGB LED channel not provided")
+            display("RGB LED channel not provided: {}", channel)
+        }
+        MismatchedChannels(red: u32, green: u32, blue: u32) {
+            description("RGB LED channels have mismatched max_brightness")
+            display("RGB LED channels have mismatched max_brightness: red={}, green={}, blue={}",
+                    red, green, blue)
+        }
+        DwellNotElapsed(remaining_ms: u64) {
+            description("minimum dwell time has not elapsed")
+            display("minimum dwell time has not elapsed: {}ms remaining", remaining_ms)
+        }
+        ParseColor(color: String) {
+            description("invalid color string")
+            display("invalid color: '{}'", color)
+        }
+        TriggerNotActive(trigger: String) {
+            description("trigger not active")
+            display("trigger not active: '{}'", trigger)
+        }
         UnsupportedTrigger(trigger: String) {
             description("trigger unsupported (kernel driver missing?)")
             display("trigger unsupported: '{}'", trigger)
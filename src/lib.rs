@@ -20,29 +20,71 @@
 //! [timing]: triggers/trait.TriggerTimer.html
 //! [heartbeat]: triggers/trait.TriggerHeartbeat.html
 //! [cpu activity]: triggers/trait.TriggerCpu.html
+//!
+//! ## `no_std`
+//!
+//! The sysfs types need `std`, which is enabled by the default `std` feature.
+//! Without it, only `Brightness`, `Easing`, and the [`colors`] module are
+//! available, for reusing the color math on microcontrollers. Their float math
+//! then comes from `libm`, so build with `default-features = false` and
+//! `features = ["libm"]`. Parsing and formatting colors as strings still needs
+//! `std`.
+//!
+//! [`colors`]: colors/index.html
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 // `error_chain!` can recurse deeply
 #![recursion_limit = "1024"]
 
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("sysfs-led needs either the `std` or the `libm` feature for float math");
+
+#[cfg(feature = "std")]
 #[macro_use]
 extern crate error_chain;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 #[cfg(test)]
 extern crate tempdir;
 
+#[cfg(feature = "std")]
+pub mod animation;
 pub mod colors;
+#[cfg(feature = "std")]
 pub mod errors;
+mod math;
+#[cfg(feature = "std")]
+mod sysfs;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "std")]
 pub mod triggers;
 
-use std::cmp;
-use std::fs::OpenOptions;
-use std::io::{Read, Write};
-use std::path::{Path, PathBuf};
+use core::cmp;
 
-use colors::Color;
-use errors::*;
+#[cfg(feature = "std")]
+pub use sysfs::*;
 
-const SYSFS_LED_CLASS: &'static str = "/sys/class/leds";
+// Relative luminance for each CIE 1931 lightness step 0..=100, scaled so that
+// 65535 is full brightness. Used by `Brightness::Cie`.
+const CIE_LUMINANCE: [u16; 101] = [
+    0, 73, 145, 218, 290, 363, 435, 508, 580, 656,
+    738, 826, 922, 1024, 1134, 1251, 1376, 1509, 1650, 1800,
+    1959, 2127, 2304, 2491, 2687, 2894, 3111, 3338, 3576, 3826,
+    4087, 4359, 4643, 4940, 5248, 5569, 5903, 6251, 6611, 6985,
+    7373, 7775, 8192, 8623, 9069, 9530, 10006, 10498, 11006, 11530,
+    12071, 12628, 13202, 13793, 14401, 15027, 15671, 16333, 17014, 17713,
+    18431, 19168, 19924, 20700, 21497, 22313, 23149, 24007, 24885, 25784,
+    26705, 27648, 28612, 29598, 30607, 31639, 32694, 33771, 34872, 35997,
+    37146, 38319, 39516, 40738, 41986, 43258, 44555, 45879, 47228, 48603,
+    50005, 51434, 52890, 54372, 55883, 57421, 58987, 60581, 62203, 63855,
+    65535,
+];
 
 
 /// Brightness of an LED
@@ -59,274 +101,207 @@ const SYSFS_LED_CLASS: &'static str = "/sys/class/leds";
 /// Realistically, however, it should never be more than 255 because that is
 /// the definition for LED_FULL. We use u32 because it makes math easier.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Brightness {
     Full,
     Off,
     Percent(u32),
     Absolute(u32),
+    /// Perceptual lightness from 0 to 100 (clamped), mapped through the
+    /// CIE 1931 lightness curve so that equal steps look equally bright
+    Cie(u32),
 }
 
 impl Brightness {
+    /// `Brightness::Full`, for use in const contexts such as static tables
+    pub const FULL: Brightness = Brightness::Full;
+    /// `Brightness::Off`, for use in const contexts such as static tables
+    pub const OFF: Brightness = Brightness::Off;
+
     pub fn to_absolute(&self, max_brightness: u32) -> u32 {
         match *self {
             Brightness::Full => max_brightness,
             Brightness::Off => 0,
             Brightness::Percent(p) => max_brightness.saturating_mul(cmp::min(p, 100)) / 100,
             Brightness::Absolute(a) => cmp::min(max_brightness, a),
+            Brightness::Cie(l) => {
+                let luminance = CIE_LUMINANCE[cmp::min(l, 100) as usize] as u64;
+                ((max_brightness as u64 * luminance + 32767) / 65535) as u32
+            }
         }
     }
 
     pub fn to_percent(&self, max_brightness: u32) -> u32 {
         match *self {
-            Brightness::Full => max_brightness,
+            Brightness::Full => 100,
             Brightness::Off => 0,
             Brightness::Percent(p) => cmp::min(p, 100),
+            Brightness::Absolute(_) if max_brightness == 0 => 0,
             Brightness::Absolute(a) => {
                 cmp::min(a, max_brightness).saturating_mul(100) / max_brightness
             }
+            Brightness::Cie(_) => self.to_absolute(100),
         }
     }
-}
-
-/// Basic functionality of an LED
-///
-/// Defines basic functionality of an LED, which is to be turned on or off at
-/// some level of brightness.
-pub trait Led {
-    /// Get the current brightness of an LED
-    fn brightness(&self) -> Result<Brightness>;
-    /// Set the brightness of an LED
-    fn set_brightness(&mut self, brightness: Brightness) -> Result<()>;
-}
-
-/// Access to an LED managed by the Linux LED sysfs class driver
-pub struct SysfsLed {
-    device_path: PathBuf,
-}
-
-impl SysfsLed {
-    /// Create a new `SysfsLed` with a given name located in the default sysfs
-    /// directory
-    pub fn new(name: &str) -> Result<SysfsLed> {
-        Self::from_path(Path::new(SYSFS_LED_CLASS).join(name))
-    }
-
-    /// Create a new `SysfsLed` with a custom path to the sysfs directory for
-    /// the LED class device
-    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<SysfsLed> {
-        require_device_files(&path)?;
-        Ok(SysfsLed { device_path: path.as_ref().to_path_buf() })
-    }
 
-    /// Return the raw max_brightness of the LED device
-    pub fn max_brightness(&self) -> Result<u32> {
-        Ok(self.sysfs_read_file("max_brightness")?.parse::<u32>()?)
+    /// Interpolate between two brightness levels against a known maximum
+    ///
+    /// Both ends are converted with `to_absolute(max_brightness)` and the
+    /// result is always `Absolute`, rounded to the nearest value. `t` is
+    /// clamped to `0.0..=1.0`, where `0.0` gives `self` and `1.0` gives
+    /// `other`.
+    pub fn lerp(&self, other: &Brightness, max_brightness: u32, t: f32) -> Brightness {
+        let t = t.max(0.0).min(1.0) as f64;
+        let from = self.to_absolute(max_brightness) as f64;
+        let to = other.to_absolute(max_brightness) as f64;
+        Brightness::Absolute(math::round(from + (to - from) * t) as u32)
     }
 
-    fn sysfs_read_file(&self, name: &str) -> Result<String> {
-        sysfs_read_file(&self.device_path, name)
+    /// Convert to a number of on-ticks within a PWM period
+    ///
+    /// For PWM-style LEDs whose `max_brightness` equals the PWM period, this
+    /// is the duty cycle in ticks. `Absolute` values are clamped to the period.
+    pub fn to_duty(&self, period_ticks: u32) -> u32 {
+        self.to_absolute(period_ticks)
     }
 
-    fn sysfs_write_file(&self, name: &str, value: &str) -> Result<()> {
-        sysfs_write_file(&self.device_path, name, value)
+    /// Convert to an absolute value against `max_brightness`, clamped into
+    /// `lower..=upper`
+    ///
+    /// Useful for keeping a fixture between a minimum visible and a maximum
+    /// safe level. If `lower` is above `upper`, `upper` wins.
+    pub fn clamp_to_range(&self, max_brightness: u32, lower: u32, upper: u32) -> Brightness {
+        let value = self.to_absolute(max_brightness);
+        Brightness::Absolute(cmp::min(cmp::max(value, lower), upper))
     }
 }
 
-impl Led for SysfsLed {
-    fn brightness(&self) -> Result<Brightness> {
-        Ok(Brightness::Absolute(self.sysfs_read_file("brightness")?.parse::<u32>()?))
-    }
-
-    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
-        let max_brightness = self.max_brightness()?;
-        let string_value = format!("{}", brightness.to_absolute(max_brightness));
-        self.sysfs_write_file("brightness", &string_value)?;
-        Ok(())
+impl Default for Brightness {
+    fn default() -> Brightness {
+        Brightness::Off
     }
 }
 
-/// Basic functionality of an LED with red, green, and blue component colors
-///
-/// By stacking multiple LEDs together, one each of red, blue, and green, it is
-/// possible to create a multicolored LED capable of showing many different
-/// colors.
-pub trait RgbLed: Led {
-    /// Get the color of the RGB LED
-    fn color(&self) -> Result<Color>;
-    /// Set the color of the RGB LED
-    fn set_color(&mut self, color: Color) -> Result<()>;
-}
-
-/// Access to an RGB LED managed by the Linux LED sysfs class driver,
-/// configured as 3 separate LEDs.
-pub struct SysfsRgbLed {
-    red: SysfsLed,
-    green: SysfsLed,
-    blue: SysfsLed,
-}
-
-impl SysfsRgbLed {
-    /// Create a new `SysfsRgbLed` from LEDs with the given names in the
-    /// default sysfs directory
-    pub fn new(red: &str, green: &str, blue: &str) -> Result<SysfsRgbLed> {
-        Self::from_leds(SysfsLed::new(red)?,
-                        SysfsLed::new(green)?,
-                        SysfsLed::new(blue)?)
-    }
-
-    /// Create a new `SysfsRgbLed` with custom paths to the sysfs directories for
-    /// the separate LED devices
-    pub fn from_path<Pr, Pg, Pb>(red: Pr, green: Pg, blue: Pb) -> Result<SysfsRgbLed>
-        where Pr: AsRef<Path>,
-              Pg: AsRef<Path>,
-              Pb: AsRef<Path>
-    {
-        Self::from_leds(SysfsLed::from_path(red)?,
-                        SysfsLed::from_path(green)?,
-                        SysfsLed::from_path(blue)?)
-    }
-
-    /// Create a new `SysfsRgbLed` from existing `SysfsLed` objects
-    pub fn from_leds(red: SysfsLed, green: SysfsLed, blue: SysfsLed) -> Result<SysfsRgbLed> {
-        Ok(SysfsRgbLed {
-            red: red,
-            green: green,
-            blue: blue,
-        })
-    }
-}
-
-impl Led for SysfsRgbLed {
-    // Brightness on an RGB LED as a whole is a bit strange since there are
-    // three LEDs making up the output. We choose to treat brightness as
-    // "lightness" in the HSL color space instead - increasing lightness will
-    // increase perceived brightness, so it's close.
-    fn brightness(&self) -> Result<Brightness> {
-        Ok(Brightness::Off)
-    }
-
-    fn set_brightness(&mut self, _brightness: Brightness) -> Result<()> {
-        Ok(())
-    }
-}
-
-impl RgbLed for SysfsRgbLed {
-    fn color(&self) -> Result<Color> {
-        // TODO: This isn't correct
-        let _red = self.red.brightness()?;
-        let _green = self.green.brightness()?;
-        let _blue = self.blue.brightness()?;
-        Ok(Color::from_rgb(0, 0, 0))
-    }
-
-    fn set_color(&mut self, color: Color) -> Result<()> {
-        let red_max = self.red.max_brightness()? as u32;
-        let green_max = self.green.max_brightness()? as u32;
-        let blue_max = self.blue.max_brightness()? as u32;
-        // TODO: This isn't correct
-        self.red.set_brightness(Brightness::Absolute(color.red() as u32))?;
-        self.green.set_brightness(Brightness::Absolute(color.green() as u32))?;
-        self.blue.set_brightness(Brightness::Absolute(color.blue() as u32))?;
-        Ok(())
-    }
+/// Easing curve applied by `Led::ease`
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Easing {
+    Linear,
+    EaseInQuad,
+    EaseOutQuad,
+    EaseInOutQuad,
 }
 
-// Make sure that the specified files exist in the given directory
-fn require_device_files<D>(dir: D) -> Result<()>
-    where D: AsRef<Path>
-{
-    for file in &["brightness", "max_brightness", "trigger"] {
-        if !dir.as_ref().join(file).is_file() {
-            bail!(ErrorKind::InvalidDevicePath(dir.as_ref().to_string_lossy().into()));
+impl Easing {
+    /// Map linear progress `t` in 0.0..=1.0 onto the curve
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseInQuad => t * t,
+            Easing::EaseOutQuad => t * (2.0 - t),
+            Easing::EaseInOutQuad if t < 0.5 => 2.0 * t * t,
+            Easing::EaseInOutQuad => -1.0 + (4.0 - 2.0 * t) * t,
         }
     }
-
-    Ok(())
 }
 
-fn sysfs_read_file(device_path: &Path, name: &str) -> Result<String> {
-    let path = device_path.join(name);
-    let mut file = OpenOptions::new().read(true)
-        .open(path)?;
-    let mut result = String::new();
-    file.read_to_string(&mut result)?;
-    Ok(result.trim().into())
-}
-
-fn sysfs_write_file(device_path: &Path, name: &str, value: &str) -> Result<()> {
-    let path = device_path.join(name);
-    let mut file = OpenOptions::new().write(true)
-        .truncate(true)
-        .create(false)
-        .open(path)?;
-    Ok(file.write_all(value.as_bytes())?)
+impl Default for Easing {
+    fn default() -> Easing {
+        Easing::Linear
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
-    use std::io::{Read, Write};
-    use std::path::Path;
-
-    use tempdir::TempDir;
-
     use super::*;
 
-    struct SysfsWrapper(TempDir);
+    #[test]
+    fn test_brightness_default() {
+        const TABLE: [Brightness; 2] = [Brightness::OFF, Brightness::FULL];
+        assert_eq!(Brightness::Off, Brightness::default());
+        assert_eq!([Brightness::Off, Brightness::Full], TABLE);
+    }
 
-    impl SysfsWrapper {
-        fn path(&self) -> &Path {
-            self.0.path()
+    #[test]
+    fn test_easing_endpoints() {
+        for easing in &[Easing::Linear,
+                        Easing::EaseInQuad,
+                        Easing::EaseOutQuad,
+                        Easing::EaseInOutQuad] {
+            assert_eq!(0.0, easing.apply(0.0));
+            assert_eq!(1.0, easing.apply(1.0));
+            assert_eq!(1.0, easing.apply(2.0));
         }
+        assert_eq!(0.5, Easing::EaseInOutQuad.apply(0.5));
+    }
 
-        fn get(&self, name: &str) -> String {
-            let mut result = String::new();
-            File::open(self.path().join(name))
-                .expect(&format!("opening {}", name))
-                .read_to_string(&mut result)
-                .expect(&format!("reading {}", name));
-            result
-        }
+    #[test]
+    fn test_cie_brightness() {
+        assert_eq!(0, Brightness::Cie(0).to_absolute(255));
+        assert_eq!(3, Brightness::Cie(10).to_absolute(255));
+        assert_eq!(47, Brightness::Cie(50).to_absolute(255));
+        assert_eq!(255, Brightness::Cie(100).to_absolute(255));
+        assert_eq!(255, Brightness::Cie(150).to_absolute(255));
+        assert_eq!(9, Brightness::Cie(8).to_absolute(1000));
+        assert_eq!(18, Brightness::Cie(50).to_percent(255));
+    }
 
-        fn set(&mut self, name: &str, value: &str) {
-            File::open(self.path().join(name))
-                .expect(&format!("opening {}", name))
-                .write_all(value.as_bytes())
-                .expect(&format!("writing {}", name));
+    #[test]
+    fn test_cie_brightness_monotonic() {
+        for max_brightness in &[255, 1000, 65535] {
+            let values: Vec<u32> = (0..101)
+                .map(|l| Brightness::Cie(l).to_absolute(*max_brightness))
+                .collect();
+            assert!(values.windows(2).all(|pair| pair[0] <= pair[1]));
         }
+        assert!(CIE_LUMINANCE.windows(2).all(|pair| pair[0] < pair[1]));
     }
 
-    macro_rules! create_sysfs_dir {
-        ( $name:expr; $( $file:expr => $value:expr );+ ) => {{
-            let tempdir = TempDir::new($name).expect("create temp dir");
-            $({
-                let mut file = File::create(tempdir.path().join($file))
-                    .expect(concat!("create ", $file, " file"));
-                file.write_all($value.as_bytes())
-                    .expect(concat!("writing ", $file, " initial value"));
-            })+
+    #[test]
+    fn test_brightness_lerp() {
+        let (off, full) = (Brightness::Off, Brightness::Full);
+        assert_eq!(Brightness::Absolute(0), off.lerp(&full, 255, 0.0));
+        assert_eq!(Brightness::Absolute(255), off.lerp(&full, 255, 1.0));
+        assert_eq!(Brightness::Absolute(128), off.lerp(&full, 255, 0.5));
+        assert_eq!(Brightness::Absolute(70),
+                   Brightness::Percent(20).lerp(&Brightness::Absolute(100), 200, 0.5));
+        assert_eq!(Brightness::Absolute(255), off.lerp(&full, 255, 1.5));
+        assert_eq!(Brightness::Absolute(0), off.lerp(&full, 255, -1.0));
+        assert_eq!(Brightness::Absolute(4_000_000_000),
+                   full.lerp(&off, 4_000_000_000, 0.0));
+    }
 
-            SysfsWrapper(tempdir)
-        }};
+    #[test]
+    fn test_to_duty() {
+        assert_eq!(1024, Brightness::Percent(25).to_duty(4096));
+        assert_eq!(4096, Brightness::Full.to_duty(4096));
+        assert_eq!(0, Brightness::Off.to_duty(4096));
+        assert_eq!(1000, Brightness::Absolute(1000).to_duty(4096));
+        assert_eq!(4096, Brightness::Absolute(5000).to_duty(4096));
+    }
+
+    #[test]
+    fn test_clamp_to_range() {
+        assert_eq!(Brightness::Absolute(10), Brightness::Off.clamp_to_range(255, 10, 200));
+        assert_eq!(Brightness::Absolute(200), Brightness::Full.clamp_to_range(255, 10, 200));
+        assert_eq!(Brightness::Absolute(128),
+                   Brightness::Percent(50).clamp_to_range(256, 10, 200));
+        assert_eq!(Brightness::Absolute(255),
+                   Brightness::Absolute(300).clamp_to_range(255, 0, 1000));
+        assert_eq!(Brightness::Absolute(5), Brightness::Off.clamp_to_range(255, 10, 5));
     }
 
+    #[cfg(feature = "serde")]
     #[test]
-    fn test_set_brightness() {
-        let harness = create_sysfs_dir!("sysfs_led_test";
-                                        "brightness" => "0";
-                                        "max_brightness" => "128";
-                                        "trigger" => "[none]");
-        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
-        let vectors = vec![(Brightness::Full, "128"),
-                           (Brightness::Percent(50), "64"),
-                           (Brightness::Percent(150), "128"),
-                           (Brightness::Absolute(0), "0"),
-                           (Brightness::Absolute(72), "72"),
-                           (Brightness::Absolute(129), "128"),
-                           (Brightness::Off, "0")];
-        for (brightness, expected) in vectors {
-            led.set_brightness(brightness).expect(&format!("setting brightness={:?}", brightness));
-            assert_eq!(expected, harness.get("brightness"));
+    fn test_brightness_serde_round_trip() {
+        let vectors = vec![Brightness::Full,
+                           Brightness::Off,
+                           Brightness::Percent(50),
+                           Brightness::Absolute(72)];
+        for brightness in vectors {
+            let json = serde_json::to_string(&brightness).expect("serialize brightness");
+            assert_eq!(brightness, serde_json::from_str(&json).expect("deserialize brightness"));
         }
     }
 }
@@ -0,0 +1,3385 @@
+// Copyright (c) 2017 Nick Stevens <nick@bitcurry.com>
+
+//! LED types backed by the Linux sysfs LED class, and the `Led` traits they
+//! implement. Everything here needs `std`.
+
+use std::cell::Cell;
+use std::cmp;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileExt;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::colors::{self, Color, Dither};
+use crate::errors::*;
+use crate::triggers::{SetTrigger, Trigger, TriggerTimer};
+use crate::{Brightness, Easing};
+
+const SYSFS_LED_CLASS: &'static str = "/sys/class/leds";
+
+// Emulated max_brightness and period of the software PWM loop in `PwmLed`
+const PWM_MAX_BRIGHTNESS: u32 = 255;
+const PWM_PERIOD_MICROS: u64 = 10_000;
+
+// Brightness updates per period in `Led::pulse_between`
+const PULSE_STEPS: u32 = 32;
+
+/// Return an LED to a known state
+///
+/// A common teardown step after running animations or triggers.
+pub trait Reset {
+    /// Deactivate any trigger and turn the LED off
+    fn reset(&mut self) -> Result<()>;
+}
+
+/// Basic functionality of an LED
+///
+/// Defines basic functionality of an LED, which is to be turned on or off at
+/// some level of brightness.
+///
+/// `Led` is object safe, so LEDs of different types can be stored together as
+/// `BoxedLed`s. Keep it that way: methods with generic parameters belong in a
+/// separate extension trait.
+pub trait Led {
+    /// Get the current brightness of an LED
+    fn brightness(&self) -> Result<Brightness>;
+    /// Set the brightness of an LED
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()>;
+    /// Get the maximum brightness that `Brightness::Absolute` values are
+    /// measured against, which defaults to 255 (the kernel's `LED_FULL`)
+    fn max_brightness(&self) -> Result<u32> {
+        Ok(255)
+    }
+    /// Set the brightness as a percentage, clamped to 100
+    fn set_percent(&mut self, percent: u32) -> Result<()> {
+        self.set_brightness(Brightness::Percent(cmp::min(percent, 100)))
+    }
+    /// Get the brightness as a percentage of `max_brightness`
+    fn percent(&self) -> Result<u32> {
+        Ok(self.brightness()?.to_percent(self.max_brightness()?))
+    }
+    /// Check whether the LED is on at any brightness
+    fn is_on(&self) -> Result<bool> {
+        Ok(match self.brightness()? {
+            Brightness::Full => true,
+            Brightness::Off => false,
+            Brightness::Percent(value) |
+            Brightness::Absolute(value) |
+            Brightness::Cie(value) => value > 0,
+        })
+    }
+    /// Turn the LED off if it is on at any brightness, otherwise turn it on at
+    /// full brightness
+    fn toggle(&mut self) -> Result<()> {
+        let on = self.is_on()?;
+        self.set_brightness(if on { Brightness::Off } else { Brightness::Full })
+    }
+    /// Blink the LED in software, blocking until done
+    ///
+    /// Turns the LED on for `on` and off for `off`, `count` times over, leaving
+    /// it off at the end.
+    fn blink(&mut self, on: Duration, off: Duration, count: u32) -> Result<()> {
+        for _ in 0..count {
+            self.set_brightness(Brightness::Full)?;
+            thread::sleep(on);
+            self.set_brightness(Brightness::Off)?;
+            thread::sleep(off);
+        }
+        Ok(())
+    }
+    /// Fade back and forth between two brightness levels until cancelled
+    ///
+    /// Runs a triangle wave from `low` up to `high` and back once every
+    /// `period`, blocking until `cancel` is set. The original brightness is
+    /// restored before returning, even if a write fails along the way. Levels
+    /// are interpolated with `Brightness::lerp` against the LED's
+    /// `max_brightness`, so mixed endpoint variants fade evenly.
+    fn pulse_between(&mut self,
+                     low: Brightness,
+                     high: Brightness,
+                     period: Duration,
+                     cancel: &AtomicBool)
+                     -> Result<()> {
+        let original = self.brightness()?;
+        let max_brightness = self.max_brightness()?;
+        let step = cmp::max(period / PULSE_STEPS, Duration::from_millis(1));
+        let period_secs = duration_secs(period);
+        let start = Instant::now();
+        let mut result = Ok(());
+        while result.is_ok() && !cancel.load(Ordering::SeqCst) {
+            let phase = if period_secs > 0.0 {
+                (duration_secs(start.elapsed()) / period_secs).fract() as f32
+            } else {
+                0.5
+            };
+            let fraction = 1.0 - (2.0 * phase - 1.0).abs();
+            result = self.set_brightness(low.lerp(&high, max_brightness, fraction));
+            thread::sleep(step);
+        }
+        let restored = self.set_brightness(original);
+        result.and(restored)
+    }
+    /// Fade from one brightness level to another along an easing curve
+    ///
+    /// Writes `steps` brightness levels spread evenly over `duration`, the
+    /// last of which is always `to`. With zero steps `to` is written at once.
+    /// Levels are interpolated with `Brightness::lerp` against the LED's
+    /// `max_brightness`, so mixed endpoint variants fade evenly.
+    fn ease(&mut self,
+            from: Brightness,
+            to: Brightness,
+            duration: Duration,
+            steps: u32,
+            easing: Easing)
+            -> Result<()> {
+        if steps == 0 {
+            return self.set_brightness(to);
+        }
+        let max_brightness = self.max_brightness()?;
+        let step = duration / steps;
+        for i in 1..=steps {
+            let fraction = easing.apply(i as f32 / steps as f32);
+            self.set_brightness(from.lerp(&to, max_brightness, fraction))?;
+            if i < steps {
+                thread::sleep(step);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An owned `Led` of any type
+pub type BoxedLed = Box<dyn Led>;
+
+/// Saved state of an LED, as captured by `SysfsLed::snapshot`
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LedState {
+    /// Brightness at the time of the snapshot
+    pub brightness: Brightness,
+    /// Active trigger at the time of the snapshot
+    pub trigger: Trigger,
+}
+
+/// How `SysfsLed::set_brightness` enforces a minimum dwell time between
+/// brightness changes
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Dwell {
+    /// Sleep until the dwell time has passed, then write
+    Wait(Duration),
+    /// Fail with `DwellNotElapsed` without writing
+    Skip(Duration),
+}
+
+/// One color channel of an RGB LED
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+/// Access to an LED managed by the Linux LED sysfs class driver
+pub struct SysfsLed {
+    pub(crate) device_path: PathBuf,
+    pub(crate) has_trigger: bool,
+    max_brightness: Cell<Option<u32>>,
+    max_brightness_override: Option<u32>,
+    min_dwell: Option<Dwell>,
+    last_change: Option<Instant>,
+}
+
+impl SysfsLed {
+    /// Create a new `SysfsLed` with a given name located in the default sysfs
+    /// directory
+    pub fn new(name: &str) -> Result<SysfsLed> {
+        Self::new_in(SYSFS_LED_CLASS, name)
+    }
+
+    /// Create a new `SysfsLed` with a given name located in a custom LED class
+    /// directory, such as a mounted sysfs in a container or a test fixture
+    pub fn new_in<P: AsRef<Path>>(root: P, name: &str) -> Result<SysfsLed> {
+        Self::from_path(root.as_ref().join(name))
+    }
+
+    /// Create a new `SysfsLed` with a custom path to the sysfs directory for
+    /// the LED class device
+    ///
+    /// The `brightness` and `max_brightness` files are required. The `trigger`
+    /// file is optional, but trigger methods will fail with
+    /// `UnsupportedTrigger` on devices without it.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<SysfsLed> {
+        require_device_files(&path)?;
+        Ok(SysfsLed {
+            device_path: path.as_ref().to_path_buf(),
+            has_trigger: path.as_ref().join("trigger").is_file(),
+            max_brightness: Cell::new(None),
+            max_brightness_override: None,
+            min_dwell: None,
+            last_change: None,
+        })
+    }
+
+    /// Open a `SysfsLed` at a custom path, requiring trigger support
+    ///
+    /// Unlike `from_path`, this also requires the `trigger` file, and a
+    /// missing `brightness`, `max_brightness`, or `trigger` file is reported
+    /// by name as `MissingAttribute`, which makes a misconfigured path much
+    /// quicker to track down. A path that isn't a directory at all is still an
+    /// `InvalidDevicePath`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SysfsLed> {
+        let dir = path.as_ref();
+        if !dir.is_dir() {
+            bail!(ErrorKind::InvalidDevicePath(dir.to_string_lossy().into()));
+        }
+        for file in &["brightness", "max_brightness", "trigger"] {
+            if !dir.join(file).is_file() {
+                bail!(ErrorKind::MissingAttribute(dir.to_string_lossy().into(), (*file).into()));
+            }
+        }
+        Self::from_path(dir)
+    }
+
+    /// Override the `max_brightness` of the LED
+    ///
+    /// Useful for pseudo-devices that report a nonsensical `max_brightness`,
+    /// or for testing. The override takes precedence over the file, so it is
+    /// what `max_brightness()` returns and what `set_brightness` scales
+    /// against; the file itself is never touched. It lasts for the life of the
+    /// `SysfsLed`, including across `resume_from`.
+    pub fn with_max_brightness(mut self, max: u32) -> SysfsLed {
+        self.max_brightness_override = Some(max);
+        self
+    }
+
+    /// Set a minimum time that must pass between brightness changes
+    ///
+    /// Rapid changes can show up as visible flicker. With a dwell set, every
+    /// brightness write either sleeps until the dwell time since the previous
+    /// change has passed or refuses the change, depending on the `Dwell`
+    /// variant. That covers `set_brightness`, `set_brightness_async`, and
+    /// `set_brightness_raw`, as well as writes through `LedPool`, `LedArray`,
+    /// `SysfsRgbLed` channels, and `SysfsMultiLed` colors. `None`, the
+    /// default, disables the check.
+    pub fn set_min_dwell(&mut self, dwell: Option<Dwell>) {
+        self.min_dwell = dwell;
+    }
+
+    /// Return the raw max_brightness of the LED device
+    ///
+    /// The value is read from the device once and cached for later calls. An
+    /// override from `with_max_brightness` is returned instead, if set.
+    pub fn max_brightness(&self) -> Result<u32> {
+        if let Some(max_brightness) = self.max_brightness_override.or(self.max_brightness.get()) {
+            return Ok(max_brightness);
+        }
+        let max_brightness = self.sysfs_read_value("max_brightness")?;
+        self.max_brightness.set(Some(max_brightness));
+        Ok(max_brightness)
+    }
+
+    /// Write a new max_brightness to the LED device, for drivers that allow
+    /// capping their output
+    ///
+    /// Most drivers expose `max_brightness` as read-only, in which case this
+    /// fails with `ReadOnlyDevice`. The cached value is only updated once the
+    /// write succeeds, and an override from `with_max_brightness` still takes
+    /// precedence over it.
+    pub fn set_max_brightness(&mut self, max: u32) -> Result<()> {
+        self.sysfs_write_file("max_brightness", &format!("{}", max))?;
+        self.max_brightness.set(Some(max));
+        Ok(())
+    }
+
+    /// Set the brightness of the LED without blocking the async executor
+    ///
+    /// Behaves like `Led::set_brightness`, but performs the sysfs IO through
+    /// `tokio::fs` and waits out any minimum dwell time with `tokio::time`.
+    /// The cached `max_brightness` is reused when available.
+    #[cfg(feature = "tokio")]
+    pub async fn set_brightness_async(&mut self, brightness: Brightness) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let max_brightness = match self.max_brightness_override.or(self.max_brightness.get()) {
+            Some(max_brightness) => max_brightness,
+            None => {
+                let path = self.device_path.join("max_brightness");
+                let contents = tokio::fs::read_to_string(&path)
+                    .await
+                    .map_err(|e| map_read_error(e, &self.device_path, "max_brightness"))?;
+                let contents = contents.trim();
+                let max_brightness = contents.parse::<u32>()
+                    .chain_err(|| {
+                        ErrorKind::InvalidFileContents("max_brightness".into(), contents.into())
+                    })?;
+                self.max_brightness.set(Some(max_brightness));
+                max_brightness
+            }
+        };
+
+        let wait = self.dwell_wait()?;
+        if wait > Duration::from_secs(0) {
+            tokio::time::sleep(wait).await;
+        }
+        let path = self.device_path.join("brightness");
+        let value = format!("{}", brightness.to_absolute(max_brightness));
+        let mut file = tokio::fs::OpenOptions::new().write(true)
+            .truncate(true)
+            .create(false)
+            .open(&path)
+            .await
+            .map_err(|e| map_write_error(e, &path))?;
+        file.write_all(value.as_bytes())
+            .await
+            .map_err(|e| map_write_error(e, &path))
+            .chain_err(|| ErrorKind::WriteFailed(path.to_string_lossy().into(), value.clone()))?;
+        // tokio completes writes in the background, so wait for them to land
+        file.flush()
+            .await
+            .map_err(|e| map_write_error(e, &path))
+            .chain_err(|| ErrorKind::WriteFailed(path.to_string_lossy().into(), value.clone()))?;
+        self.last_change = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Return the path of the LED class device, as given when it was created
+    ///
+    /// Useful for logging, or for reaching attribute files of the device that
+    /// this crate doesn't model.
+    pub fn device_path(&self) -> &Path {
+        &self.device_path
+    }
+
+    /// Return the canonical path of the LED class device
+    ///
+    /// Entries in `/sys/class/leds` are symlinks into the device tree; this
+    /// resolves them to the real path, which is useful for udev matching.
+    pub fn canonical_path(&self) -> Result<PathBuf> {
+        Ok(fs::canonicalize(&self.device_path)?)
+    }
+
+    /// Check whether the `brightness` file is writable at all
+    ///
+    /// Some devices are purely trigger-driven and expose `brightness` without
+    /// any write permission bits. This only looks at those bits, so a writable
+    /// device can still fail with `PermissionDenied` for an unprivileged
+    /// process.
+    pub fn is_writable(&self) -> Result<bool> {
+        let metadata = fs::metadata(self.device_path.join("brightness"))?;
+        Ok(!metadata.permissions().readonly())
+    }
+
+    /// Read the physical color of a single-color LED
+    ///
+    /// Newer kernels name the color of the LED (`red`, `green`, `amber`, and
+    /// so on) in the optional `color` attribute. Returns `None` if the device
+    /// doesn't have one.
+    pub fn physical_color(&self) -> Result<Option<String>> {
+        if !self.device_path.join("color").is_file() {
+            return Ok(None);
+        }
+        Ok(Some(self.sysfs_read_file("color")?))
+    }
+
+    /// Read the `uevent` attributes of the LED's parent device
+    ///
+    /// Parses the `KEY=VALUE` lines of `device/uevent`, which identify the
+    /// device the LED belongs to (driver, bus address, and so on). Returns an
+    /// empty map if the LED has no parent device.
+    pub fn uevent(&self) -> Result<HashMap<String, String>> {
+        let mut result = HashMap::new();
+        if !self.device_path.join("device/uevent").is_file() {
+            return Ok(result);
+        }
+        for line in self.sysfs_read_file("device/uevent")?.lines() {
+            if let Some(index) = line.find('=') {
+                result.insert(line[..index].into(), line[index + 1..].into());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Capture the current brightness and trigger of the LED
+    pub fn snapshot(&self) -> Result<LedState> {
+        Ok(LedState {
+            brightness: self.brightness()?,
+            trigger: self.active_trigger()?,
+        })
+    }
+
+    /// Restore a state previously captured with `snapshot`
+    ///
+    /// The trigger is applied first. The brightness is only restored when the
+    /// trigger is `Trigger::None`, since writing brightness would otherwise
+    /// disable the trigger.
+    pub fn restore(&mut self, state: &LedState) -> Result<()> {
+        self.set_trigger(&state.trigger)?;
+        if state.trigger == Trigger::None {
+            self.set_brightness(state.brightness)?;
+        }
+        Ok(())
+    }
+
+    /// Restore a saved state after a system suspend/resume cycle
+    ///
+    /// Triggers may be reset on resume, and the LED device may have been
+    /// removed and re-enumerated, so the device path is validated again before
+    /// the state is restored as in `restore`. The path itself is not looked up
+    /// again, so a re-enumerated device must reappear under the same name.
+    /// The cached `max_brightness` is re-read, but an override from
+    /// `with_max_brightness` is kept.
+    pub fn resume_from(&mut self, state: &LedState) -> Result<()> {
+        require_device_files(&self.device_path)?;
+        self.has_trigger = self.device_path.join("trigger").is_file();
+        self.max_brightness.set(None);
+        self.restore(state)
+    }
+
+    /// Set the brightness of `other` to match this LED
+    ///
+    /// The brightness is rescaled so that both LEDs are at the same fraction of
+    /// their respective `max_brightness`.
+    pub fn mirror_to(&self, other: &mut SysfsLed) -> Result<()> {
+        let max_brightness = self.max_brightness()?;
+        let value = self.brightness()?.to_absolute(max_brightness);
+        let rescaled = rescale(value, max_brightness, other.max_brightness()?);
+        other.set_brightness(Brightness::Absolute(rescaled))
+    }
+
+    /// Write a raw hardware brightness value, clamped to `max_brightness`
+    ///
+    /// For tight loops where the caller has already computed hardware values.
+    /// Nothing is converted, but any minimum dwell time is still enforced, as
+    /// with `set_brightness`.
+    pub fn set_brightness_raw(&mut self, raw: u32) -> Result<()> {
+        let value = format!("{}", cmp::min(raw, self.max_brightness()?));
+        self.paced_write(|led| led.sysfs_write_file("brightness", &value))
+    }
+
+    /// Read the last brightness set by the hardware itself
+    ///
+    /// Some devices change brightness on their own, for example in response to
+    /// a hotkey, and report it in the optional `brightness_hw_changed`
+    /// attribute. Returns `None` if the device has no such attribute.
+    pub fn hw_changed_brightness(&self) -> Result<Option<Brightness>> {
+        if !self.device_path.join("brightness_hw_changed").is_file() {
+            return Ok(None);
+        }
+        Ok(Some(Brightness::Absolute(self.sysfs_read_value("brightness_hw_changed")?)))
+    }
+
+    /// Set the brightness only if it differs from the current brightness
+    ///
+    /// Returns whether a write was made. Skipping redundant writes avoids
+    /// needless sysfs traffic from control loops that repeat the same value.
+    pub fn set_brightness_if_changed(&mut self, brightness: Brightness) -> Result<bool> {
+        let max_brightness = self.max_brightness()?;
+        let current = self.brightness()?.to_absolute(max_brightness);
+        if current == brightness.to_absolute(max_brightness) {
+            return Ok(false);
+        }
+        self.set_brightness(brightness)?;
+        Ok(true)
+    }
+
+    /// Drive the brightness from a sampled source until cancelled
+    ///
+    /// Every `interval`, `source` is polled and its result written with
+    /// `set_brightness_if_changed`, blocking until `cancel` is set. This is a
+    /// simple control loop for LEDs that reflect some external value.
+    pub fn drive_from<F>(&mut self,
+                         mut source: F,
+                         interval: Duration,
+                         cancel: &AtomicBool)
+                         -> Result<()>
+        where F: FnMut() -> Brightness
+    {
+        while !cancel.load(Ordering::SeqCst) {
+            self.set_brightness_if_changed(source())?;
+            thread::sleep(interval);
+        }
+        Ok(())
+    }
+
+    /// Blink the LED, using the kernel `timer` trigger when available
+    ///
+    /// If the device offers the `timer` trigger it is configured with the
+    /// given delays, rounded to milliseconds. This returns immediately and
+    /// costs no CPU, but blinks until the trigger is changed and ignores
+    /// `count`. Otherwise this falls back to the blocking `Led::blink`.
+    pub fn hw_blink(&mut self, on: Duration, off: Duration, count: u32) -> Result<()> {
+        if self.available_triggers()?.iter().any(|t| t == "timer") {
+            let millis = |d: Duration| d.as_secs() * 1000 + d.subsec_millis() as u64;
+            return self.timer(millis(on), millis(off));
+        }
+        self.blink(on, off, count)
+    }
+
+    /// Drive the LED with temporal dithering for a number of frames
+    ///
+    /// For each frame the LED is switched fully on or off as decided by
+    /// `dither`, then the thread sleeps for `frame_time`, so this blocks for
+    /// `frames * frame_time` in total. The brightness is only written when it
+    /// changes between frames.
+    pub fn dither(&mut self, dither: &Dither, frames: u64, frame_time: Duration) -> Result<()> {
+        let mut last = None;
+        for frame in 0..frames {
+            let on = dither.is_on(frame);
+            if last != Some(on) {
+                self.set_brightness(if on { Brightness::Full } else { Brightness::Off })?;
+                last = Some(on);
+            }
+            thread::sleep(frame_time);
+        }
+        Ok(())
+    }
+
+    /// Read an arbitrary attribute file of the LED class device
+    ///
+    /// An escape hatch for driver attributes this crate doesn't model. The
+    /// contents are returned with surrounding whitespace trimmed. Names
+    /// containing `/` or `..` are rejected with `InvalidAttributeName`, so
+    /// only files directly inside the device directory can be reached.
+    pub fn read_attribute(&self, name: &str) -> Result<String> {
+        check_attribute_name(name)?;
+        self.sysfs_read_file(name)
+    }
+
+    /// Write an arbitrary attribute file of the LED class device
+    ///
+    /// The counterpart to `read_attribute`, with the same restrictions on
+    /// `name`.
+    pub fn write_attribute(&mut self, name: &str, value: &str) -> Result<()> {
+        check_attribute_name(name)?;
+        self.sysfs_write_file(name, value)
+    }
+
+    pub(crate) fn sysfs_read_file(&self, name: &str) -> Result<String> {
+        sysfs_read_file(&self.device_path, name)
+    }
+
+    pub(crate) fn sysfs_read_value<T>(&self, name: &str) -> Result<T>
+        where T: FromStr,
+              T::Err: ::std::error::Error + Send + Sync + 'static
+    {
+        let contents = self.sysfs_read_file(name)?;
+        contents.parse::<T>()
+            .chain_err(|| ErrorKind::InvalidFileContents(name.into(), contents.clone()))
+    }
+
+    pub(crate) fn sysfs_write_file(&self, name: &str, value: &str) -> Result<()> {
+        sysfs_write_file(&self.device_path, name, value)
+    }
+
+    // How long to wait before the next brightness change to honor the minimum
+    // dwell time. Fails with `DwellNotElapsed` instead for `Dwell::Skip`.
+    fn dwell_wait(&self) -> Result<Duration> {
+        let (dwell, last_change) = match (self.min_dwell, self.last_change) {
+            (Some(dwell), Some(last_change)) => (dwell, last_change),
+            _ => return Ok(Duration::from_secs(0)),
+        };
+        let elapsed = last_change.elapsed();
+        match dwell {
+            Dwell::Wait(min) if elapsed < min => Ok(min - elapsed),
+            Dwell::Skip(min) if elapsed < min => {
+                let remaining = min - elapsed;
+                let remaining_ms = remaining.as_secs() * 1000 +
+                                   remaining.subsec_nanos() as u64 / 1_000_000;
+                bail!(ErrorKind::DwellNotElapsed(remaining_ms));
+            }
+            _ => Ok(Duration::from_secs(0)),
+        }
+    }
+
+    // Perform a brightness write once the minimum dwell time allows it, and
+    // record the change. Every brightness writer goes through here (or mirrors
+    // it, for async) so that mixing them can't defeat the dwell.
+    fn paced_write<F>(&mut self, write: F) -> Result<()>
+        where F: FnOnce(&mut SysfsLed) -> Result<()>
+    {
+        let wait = self.dwell_wait()?;
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+        write(self)?;
+        self.last_change = Some(Instant::now());
+        Ok(())
+    }
+}
+
+impl Led for SysfsLed {
+    /// Get the current brightness of the LED as `Brightness::Absolute`
+    ///
+    /// Some drivers transiently report a brightness above `max_brightness`,
+    /// for example while a trigger is active, so the value is clamped to
+    /// `max_brightness`.
+    fn brightness(&self) -> Result<Brightness> {
+        let brightness = self.sysfs_read_value::<u32>("brightness")?;
+        Ok(Brightness::Absolute(cmp::min(brightness, self.max_brightness()?)))
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        let max_brightness = self.max_brightness()?;
+        let string_value = format!("{}", brightness.to_absolute(max_brightness));
+        self.paced_write(|led| led.sysfs_write_file("brightness", &string_value))
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        SysfsLed::max_brightness(self)
+    }
+}
+
+impl Reset for SysfsLed {
+    /// Clears the trigger and its parameters as `reset_trigger` does, if the
+    /// device supports triggers, then sets the brightness to `Off`
+    fn reset(&mut self) -> Result<()> {
+        if self.has_trigger {
+            self.reset_trigger()?;
+        }
+        self.set_brightness(Brightness::Off)
+    }
+}
+
+/// Basic functionality of an LED with red, green, and blue component colors
+///
+/// By stacking multiple LEDs together, one each of red, blue, and green, it is
+/// possible to create a multicolored LED capable of showing many different
+/// colors.
+pub trait RgbLed: Led {
+    /// Get the color of the RGB LED
+    fn color(&self) -> Result<Color>;
+    /// Set the color of the RGB LED
+    fn set_color(&mut self, color: Color) -> Result<()>;
+    /// Change the hue of the RGB LED, keeping its saturation and value
+    fn set_hue(&mut self, hue: u8) -> Result<()> {
+        let (_, saturation, value) = self.color()?.to_hsv();
+        self.set_color(Color::from_hsv(hue, saturation, value))
+    }
+}
+
+/// Access to an RGB LED managed by the Linux LED sysfs class driver,
+/// configured as 3 separate LEDs.
+pub struct SysfsRgbLed {
+    pub(crate) red: SysfsLed,
+    pub(crate) green: SysfsLed,
+    pub(crate) blue: SysfsLed,
+    maxes: (u32, u32, u32),
+    color: Color,
+    level: Brightness,
+    master: Option<SysfsLed>,
+}
+
+impl SysfsRgbLed {
+    /// Create a new `SysfsRgbLed` from LEDs with the given names in the
+    /// default sysfs directory
+    pub fn new(red: &str, green: &str, blue: &str) -> Result<SysfsRgbLed> {
+        Self::new_in(SYSFS_LED_CLASS, red, green, blue)
+    }
+
+    /// Create a new `SysfsRgbLed` from LEDs with the given names in a custom
+    /// LED class directory
+    pub fn new_in<P: AsRef<Path>>(root: P,
+                                  red: &str,
+                                  green: &str,
+                                  blue: &str)
+                                  -> Result<SysfsRgbLed> {
+        Self::from_leds(SysfsLed::new_in(&root, red)?,
+                        SysfsLed::new_in(&root, green)?,
+                        SysfsLed::new_in(&root, blue)?)
+    }
+
+    /// Find and assemble the channels of an RGB LED in the default sysfs
+    /// directory by their common name prefix
+    ///
+    /// Each channel is the LED named `<prefix>:red`, `<prefix>:green`, or
+    /// `<prefix>:blue` if there is one, or otherwise any LED whose name starts
+    /// with `<prefix>:` and whose `color` attribute names the channel. Fails
+    /// with `MissingChannel` for the first channel that can't be found.
+    ///
+    /// If there is also an LED named just `<prefix>`, it is used as the master
+    /// device (see `SysfsRgbLedBuilder::master`), so brightness changes are
+    /// scaled in hardware. Otherwise brightness dims the color.
+    pub fn discover(prefix: &str) -> Result<SysfsRgbLed> {
+        Self::discover_in(SYSFS_LED_CLASS, prefix)
+    }
+
+    /// Like `discover`, but searching a custom LED class directory
+    pub fn discover_in<P: AsRef<Path>>(root: P, prefix: &str) -> Result<SysfsRgbLed> {
+        let root = root.as_ref();
+        let mut builder = SysfsRgbLed::builder()
+            .red(discover_channel(root, prefix, "red")?)
+            .green(discover_channel(root, prefix, "green")?)
+            .blue(discover_channel(root, prefix, "blue")?);
+        // Anything at `<prefix>` that doesn't open as an LED isn't a master
+        if let Ok(master) = SysfsLed::new_in(root, prefix) {
+            builder = builder.master(master);
+        }
+        builder.build()
+    }
+
+    /// Create a new `SysfsRgbLed` with custom paths to the sysfs directories for
+    /// the separate LED devices
+    pub fn from_path<Pr, Pg, Pb>(red: Pr, green: Pg, blue: Pb) -> Result<SysfsRgbLed>
+        where Pr: AsRef<Path>,
+              Pg: AsRef<Path>,
+              Pb: AsRef<Path>
+    {
+        Self::from_leds(SysfsLed::from_path(red)?,
+                        SysfsLed::from_path(green)?,
+                        SysfsLed::from_path(blue)?)
+    }
+
+    /// Create a new `SysfsRgbLed` from existing `SysfsLed` objects
+    ///
+    /// The `max_brightness` of each channel is read once here and reused for
+    /// every color change. The current color of the LED is read as well, and
+    /// the brightness starts out as `Brightness::Full`.
+    ///
+    /// Use `builder` to give the LED a master device.
+    pub fn from_leds(red: SysfsLed, green: SysfsLed, blue: SysfsLed) -> Result<SysfsRgbLed> {
+        let maxes = (red.max_brightness()?, green.max_brightness()?, blue.max_brightness()?);
+        let color = Color::from_rgb(read_channel(&red, maxes.0)?,
+                                    read_channel(&green, maxes.1)?,
+                                    read_channel(&blue, maxes.2)?);
+        Ok(SysfsRgbLed {
+            red: red,
+            green: green,
+            blue: blue,
+            maxes: maxes,
+            color: color,
+            level: Brightness::Full,
+            master: None,
+        })
+    }
+
+    /// Get the `(red, green, blue)` channel `max_brightness` values cached at
+    /// construction
+    pub fn channel_maxes(&self) -> (u32, u32, u32) {
+        self.maxes
+    }
+
+    /// Get the current color as hue, saturation, and value components
+    ///
+    /// Uses the scale of `Color::to_hsv`, which is handy for initializing HSV
+    /// controls from the LED's state.
+    pub fn hsv(&self) -> Result<(u8, u8, u8)> {
+        Ok(self.color()?.to_hsv())
+    }
+
+    /// Get the current color as hue, saturation, and lightness components
+    ///
+    /// Uses the scale of `Color::to_hsl`.
+    pub fn hsl(&self) -> Result<(u8, u8, u8)> {
+        Ok(self.color()?.to_hsl())
+    }
+
+    /// Read back the color the hardware is actually displaying
+    ///
+    /// Each channel can only show as many levels as its `max_brightness`
+    /// allows, so this can differ from `color` when a channel has fewer than
+    /// 256 levels. Comparing the two shows where a fade will band. Without a
+    /// master device, the result also includes any dimming from
+    /// `set_brightness`.
+    pub fn effective_color(&self) -> Result<Color> {
+        Ok(Color::from_rgb(read_channel(&self.red, self.maxes.0)?,
+                           read_channel(&self.green, self.maxes.1)?,
+                           read_channel(&self.blue, self.maxes.2)?))
+    }
+
+    /// Iterate over the underlying red, green and blue channel LEDs, in that
+    /// order
+    pub fn channels(&self) -> impl Iterator<Item = &SysfsLed> {
+        iter::once(&self.red).chain(iter::once(&self.green)).chain(iter::once(&self.blue))
+    }
+
+    /// Mutably iterate over the underlying red, green and blue channel LEDs,
+    /// in that order
+    ///
+    /// Writing a channel's brightness directly is not reflected in `color`.
+    pub fn channels_mut(&mut self) -> impl Iterator<Item = &mut SysfsLed> {
+        iter::once(&mut self.red)
+            .chain(iter::once(&mut self.green))
+            .chain(iter::once(&mut self.blue))
+    }
+
+    /// Get the brightness of a single channel, as `Brightness::Absolute`
+    /// against that channel's `max_brightness`
+    pub fn channel_brightness(&self, channel: Channel) -> Result<Brightness> {
+        self.channel(channel).brightness()
+    }
+
+    /// Set the brightness of a single channel, scaled to that channel's
+    /// `max_brightness`
+    ///
+    /// Handy for calibration and testing. Like writing through
+    /// `channels_mut`, the change is not reflected in `color`.
+    pub fn set_channel(&mut self, channel: Channel, brightness: Brightness) -> Result<()> {
+        self.channel_mut(channel).set_brightness(brightness)
+    }
+
+    fn channel(&self, channel: Channel) -> &SysfsLed {
+        match channel {
+            Channel::Red => &self.red,
+            Channel::Green => &self.green,
+            Channel::Blue => &self.blue,
+        }
+    }
+
+    fn channel_mut(&mut self, channel: Channel) -> &mut SysfsLed {
+        match channel {
+            Channel::Red => &mut self.red,
+            Channel::Green => &mut self.green,
+            Channel::Blue => &mut self.blue,
+        }
+    }
+
+    /// Create a builder for a `SysfsRgbLed` that can validate its channels
+    pub fn builder() -> SysfsRgbLedBuilder {
+        SysfsRgbLedBuilder::default()
+    }
+
+    /// Set the color from hue, saturation, and value components
+    ///
+    /// Shorthand for `set_color(Color::from_hsv(hue, saturation, value))`.
+    pub fn set_color_hsv(&mut self, hue: u8, saturation: u8, value: u8) -> Result<()> {
+        self.set_color(Color::from_hsv(hue, saturation, value))
+    }
+
+    /// Set the color from hue, saturation, and lightness components
+    ///
+    /// Shorthand for `set_color(Color::from_hsl(hue, saturation, lightness))`.
+    pub fn set_color_hsl(&mut self, hue: u8, saturation: u8, lightness: u8) -> Result<()> {
+        self.set_color(Color::from_hsl(hue, saturation, lightness))
+    }
+
+    /// Set the color exactly, ignoring the current brightness
+    ///
+    /// Unlike `set_color`, the color is written to the channels without being
+    /// dimmed. It is still remembered, so a later `set_brightness` will dim it.
+    pub fn set_color_raw(&mut self, color: Color) -> Result<()> {
+        self.color = color;
+        self.write_color(color)
+    }
+
+    // Write a color to the channels, scaling each to its max_brightness
+    fn write_color(&mut self, color: Color) -> Result<()> {
+        let (red_max, green_max, blue_max) = self.maxes;
+        write_scaled_channel(&mut self.red, color.red(), red_max, false, None)?;
+        write_scaled_channel(&mut self.green, color.green(), green_max, false, None)?;
+        write_scaled_channel(&mut self.blue, color.blue(), blue_max, false, None)?;
+        Ok(())
+    }
+}
+
+/// Builder for a `SysfsRgbLed`, created with `SysfsRgbLed::builder`
+///
+/// All three channels must be provided. By default channels with any
+/// `max_brightness` are accepted; use `max_ratio` to reject channels whose
+/// resolutions differ wildly.
+#[derive(Default)]
+pub struct SysfsRgbLedBuilder {
+    red: Option<SysfsLed>,
+    green: Option<SysfsLed>,
+    blue: Option<SysfsLed>,
+    master: Option<SysfsLed>,
+    max_ratio: Option<u32>,
+}
+
+impl SysfsRgbLedBuilder {
+    /// Set the LED for the red channel
+    pub fn red(mut self, led: SysfsLed) -> SysfsRgbLedBuilder {
+        self.red = Some(led);
+        self
+    }
+
+    /// Set the LED for the green channel
+    pub fn green(mut self, led: SysfsLed) -> SysfsRgbLedBuilder {
+        self.green = Some(led);
+        self
+    }
+
+    /// Set the LED for the blue channel
+    pub fn blue(mut self, led: SysfsLed) -> SysfsRgbLedBuilder {
+        self.blue = Some(led);
+        self
+    }
+
+    /// Set a master LED device whose `brightness` scales every channel in
+    /// hardware
+    ///
+    /// Some RGB devices expose one. With a master, `set_brightness` writes the
+    /// master's `brightness` and colors are written to the channels undimmed.
+    pub fn master(mut self, led: SysfsLed) -> SysfsRgbLedBuilder {
+        self.master = Some(led);
+        self
+    }
+
+    /// Fail to build if the largest channel `max_brightness` is more than
+    /// `ratio` times the smallest
+    pub fn max_ratio(mut self, ratio: u32) -> SysfsRgbLedBuilder {
+        self.max_ratio = Some(ratio);
+        self
+    }
+
+    /// Build the `SysfsRgbLed`, reading and validating each channel's
+    /// `max_brightness`
+    pub fn build(self) -> Result<SysfsRgbLed> {
+        let red = self.red.ok_or_else(|| ErrorKind::MissingChannel("red".into()))?;
+        let green = self.green.ok_or_else(|| ErrorKind::MissingChannel("green".into()))?;
+        let blue = self.blue.ok_or_else(|| ErrorKind::MissingChannel("blue".into()))?;
+        let mut led = SysfsRgbLed::from_leds(red, green, blue)?;
+        led.master = self.master;
+
+        if let Some(ratio) = self.max_ratio {
+            let (red_max, green_max, blue_max) = led.maxes;
+            let largest = cmp::max(cmp::max(red_max, green_max), blue_max) as u64;
+            let smallest = cmp::min(cmp::min(red_max, green_max), blue_max) as u64;
+            if largest > smallest * ratio as u64 {
+                bail!(ErrorKind::MismatchedChannels(red_max, green_max, blue_max));
+            }
+        }
+
+        Ok(led)
+    }
+}
+
+impl Reset for SysfsRgbLed {
+    /// Resets every channel and sets the brightness back to `Full`
+    ///
+    /// A master device has its trigger cleared and is set to full brightness,
+    /// since the channels alone now keep the LED dark.
+    fn reset(&mut self) -> Result<()> {
+        self.color = colors::BLACK;
+        self.level = Brightness::Full;
+        let master = match self.master {
+            Some(ref mut master) => {
+                let cleared = if master.has_trigger { master.reset_trigger() } else { Ok(()) };
+                cleared.and(master.set_brightness(Brightness::Full))
+            }
+            None => Ok(()),
+        };
+        self.red.reset()
+            .and(self.green.reset())
+            .and(self.blue.reset())
+            .and(master)
+    }
+}
+
+impl Led for SysfsRgbLed {
+    // Brightness on an RGB LED as a whole is a bit strange since there are
+    // three LEDs making up the output. If the LED was built with a master
+    // device, the hardware takes care of it. Otherwise we treat brightness as a
+    // scale factor applied to every channel of the current color, measured
+    // against a maximum of 255. Either way the brightness is remembered, so
+    // colors set later are dimmed to match.
+    fn brightness(&self) -> Result<Brightness> {
+        Ok(self.level)
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        self.level = brightness;
+        if let Some(ref mut master) = self.master {
+            return master.set_brightness(brightness);
+        }
+        let color = self.color.scale_u8(brightness.to_absolute(255) as u8);
+        self.write_color(color)
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        match self.master {
+            Some(ref master) => master.max_brightness(),
+            None => Ok(255),
+        }
+    }
+}
+
+impl RgbLed for SysfsRgbLed {
+    /// Get the most recently set color of the RGB LED, before any dimming from
+    /// `set_brightness` is applied
+    fn color(&self) -> Result<Color> {
+        Ok(self.color)
+    }
+
+    /// Set the color of the RGB LED, dimmed to the current brightness
+    fn set_color(&mut self, color: Color) -> Result<()> {
+        self.color = color;
+        if self.master.is_some() {
+            return self.write_color(color);
+        }
+        self.write_color(color.scale_u8(self.level.to_absolute(255) as u8))
+    }
+}
+
+/// Access to an RGB LED managed by the Linux multicolor LED class driver
+///
+/// Newer kernels expose an RGB LED as a single device, with the color given by
+/// per-channel values in `multi_intensity` and the order of those channels
+/// listed in `multi_index`. Each intensity is measured against the device's
+/// `max_brightness`, and the overall `brightness` scales all of them.
+pub struct SysfsMultiLed {
+    led: SysfsLed,
+    // Positions of the red, green, and blue channels in `multi_intensity`
+    indices: (usize, usize, usize),
+}
+
+impl SysfsMultiLed {
+    /// Create a new `SysfsMultiLed` with a given name located in the default
+    /// sysfs directory
+    pub fn new(name: &str) -> Result<SysfsMultiLed> {
+        Self::new_in(SYSFS_LED_CLASS, name)
+    }
+
+    /// Create a new `SysfsMultiLed` with a given name located in a custom LED
+    /// class directory
+    pub fn new_in<P: AsRef<Path>>(root: P, name: &str) -> Result<SysfsMultiLed> {
+        Self::from_path(root.as_ref().join(name))
+    }
+
+    /// Create a new `SysfsMultiLed` with a custom path to the sysfs directory
+    /// for the LED class device
+    ///
+    /// Fails with `MissingChannel` if `multi_index` doesn't list all of `red`,
+    /// `green`, and `blue`. Any other channels are left untouched.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<SysfsMultiLed> {
+        let led = SysfsLed::from_path(&path)?;
+        if !path.as_ref().join("multi_intensity").is_file() {
+            bail!(ErrorKind::InvalidDevicePath(path.as_ref().to_string_lossy().into()));
+        }
+        let index = led.sysfs_read_file("multi_index")?;
+        let position = |channel: &str| {
+            index.split_whitespace()
+                .position(|name| name == channel)
+                .ok_or_else(|| Error::from(ErrorKind::MissingChannel(channel.into())))
+        };
+        let indices = (position("red")?, position("green")?, position("blue")?);
+        Ok(SysfsMultiLed {
+            led: led,
+            indices: indices,
+        })
+    }
+
+    /// Set a minimum time that must pass between brightness or color changes
+    ///
+    /// See `SysfsLed::set_min_dwell`. Color changes count as changes too.
+    pub fn set_min_dwell(&mut self, dwell: Option<Dwell>) {
+        self.led.set_min_dwell(dwell);
+    }
+
+    // Read the raw per-channel values of `multi_intensity`
+    fn intensities(&self) -> Result<Vec<u32>> {
+        let contents = self.led.sysfs_read_file("multi_intensity")?;
+        contents.split_whitespace()
+            .map(|value| value.parse::<u32>())
+            .collect::<::std::result::Result<Vec<_>, _>>()
+            .chain_err(|| {
+                ErrorKind::InvalidFileContents("multi_intensity".into(), contents.clone())
+            })
+    }
+}
+
+impl Led for SysfsMultiLed {
+    fn brightness(&self) -> Result<Brightness> {
+        self.led.brightness()
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        self.led.set_brightness(brightness)
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        self.led.max_brightness()
+    }
+}
+
+impl RgbLed for SysfsMultiLed {
+    fn color(&self) -> Result<Color> {
+        let max_brightness = self.led.max_brightness()?;
+        let intensities = self.intensities()?;
+        let channel = |index: usize| -> Result<u8> {
+            match intensities.get(index) {
+                Some(&value) => Ok(rescale(value, max_brightness, 255) as u8),
+                None => bail!(ErrorKind::InvalidFileContents("multi_intensity".into(),
+                                                             format!("{:?}", intensities))),
+            }
+        };
+        let (red, green, blue) = self.indices;
+        Ok(Color::from_rgb(channel(red)?, channel(green)?, channel(blue)?))
+    }
+
+    fn set_color(&mut self, color: Color) -> Result<()> {
+        let max_brightness = self.led.max_brightness()?;
+        let mut intensities = self.intensities()?;
+        let (red, green, blue) = self.indices;
+        let channels = [(red, color.red()), (green, color.green()), (blue, color.blue())];
+        for &(index, value) in &channels {
+            if index >= intensities.len() {
+                intensities.resize(index + 1, 0);
+            }
+            intensities[index] = rescale(value as u32, 255, max_brightness);
+        }
+        let values: Vec<String> = intensities.iter().map(|value| value.to_string()).collect();
+        let values = values.join(" ");
+        self.led.paced_write(|led| led.sysfs_write_file("multi_intensity", &values))
+    }
+}
+
+/// An array of `SysfsLed`s that can be updated together
+///
+/// Each sysfs attribute is a separate file, so there is no way to combine the
+/// writes to several LEDs into a single syscall. Instead, `set_all` reads every
+/// `max_brightness` and computes every value up front, then issues the
+/// `brightness` writes back-to-back so the LEDs change as close together as
+/// possible.
+pub struct LedArray {
+    leds: Vec<SysfsLed>,
+}
+
+impl LedArray {
+    /// Create a new `LedArray` from existing `SysfsLed` objects
+    pub fn new(leds: Vec<SysfsLed>) -> LedArray {
+        LedArray { leds: leds }
+    }
+
+    /// Set every LED in the array to the same brightness
+    ///
+    /// Any minimum dwell times are waited out once, before the first write.
+    /// Writing stops at the first error.
+    pub fn set_all(&mut self, brightness: Brightness) -> Result<()> {
+        let mut values = Vec::with_capacity(self.leds.len());
+        let mut wait = Duration::from_secs(0);
+        for led in &self.leds {
+            values.push(format!("{}", brightness.to_absolute(led.max_brightness()?)));
+            wait = cmp::max(wait, led.dwell_wait()?);
+        }
+        // Wait out the longest minimum dwell once, so the writes stay together
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+        for (led, value) in self.leds.iter_mut().zip(values.iter()) {
+            led.sysfs_write_file("brightness", value)?;
+            led.last_change = Some(Instant::now());
+        }
+        Ok(())
+    }
+}
+
+/// A pool of `SysfsLed`s whose `brightness` files are kept open
+///
+/// Opening and closing `brightness` on every write dominates the cost of
+/// driving many LEDs, such as an LED matrix. The pool opens each file once and
+/// writes each value at offset 0 with a single `pwrite`, which cuts each
+/// update down to the write itself. Sysfs takes every write as a complete
+/// value, so there is no need to truncate or seek.
+///
+/// The tradeoff is one open file descriptor per LED for as long as the pool
+/// lives, which can hit the process's descriptor limit for very large pools.
+/// The descriptors are closed when the pool is dropped.
+pub struct LedPool {
+    leds: Vec<(SysfsLed, File)>,
+}
+
+impl LedPool {
+    /// Create a new `LedPool`, opening the `brightness` file of every LED
+    pub fn new(leds: Vec<SysfsLed>) -> Result<LedPool> {
+        let mut pool = Vec::with_capacity(leds.len());
+        for led in leds {
+            let path = led.device_path.join("brightness");
+            let file = OpenOptions::new().write(true)
+                .create(false)
+                .open(&path)
+                .map_err(|e| map_write_error(e, &path))?;
+            pool.push((led, file));
+        }
+        Ok(LedPool { leds: pool })
+    }
+
+    /// Number of LEDs in the pool
+    pub fn len(&self) -> usize {
+        self.leds.len()
+    }
+
+    /// Check whether the pool has no LEDs
+    pub fn is_empty(&self) -> bool {
+        self.leds.is_empty()
+    }
+
+    /// Set the brightness of the LED at `index`
+    ///
+    /// Any minimum dwell time set on the LED is honored, as with
+    /// `Led::set_brightness`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set(&mut self, index: usize, brightness: Brightness) -> Result<()> {
+        let (ref mut led, ref file) = self.leds[index];
+        let value = format!("{}", brightness.to_absolute(led.max_brightness()?));
+        let path = led.device_path.join("brightness");
+        led.paced_write(|_| {
+            file.write_at(value.as_bytes(), 0)
+                .and_then(|written| if written == value.len() {
+                    Ok(())
+                } else {
+                    Err(io::Error::new(io::ErrorKind::WriteZero, "short write"))
+                })
+                .map_err(|e| map_write_error(e, &path))
+                .chain_err(|| ErrorKind::WriteFailed(path.to_string_lossy().into(), value.clone()))
+        })
+    }
+
+    /// Set every LED in the pool to the same brightness
+    ///
+    /// Writing stops at the first error.
+    pub fn set_all(&mut self, brightness: Brightness) -> Result<()> {
+        for index in 0..self.leds.len() {
+            self.set(index, brightness)?;
+        }
+        Ok(())
+    }
+}
+
+/// A group of LEDs driven together as a single `Led`
+///
+/// Setting the brightness of the group sets it on every member. If any member
+/// fails, the remaining members are still updated and the first error is
+/// returned. The brightness of the group is the brightness of its first
+/// member, or `Brightness::Off` if the group is empty.
+#[derive(Default)]
+pub struct LedGroup {
+    leds: Vec<BoxedLed>,
+}
+
+impl LedGroup {
+    /// Create a new, empty `LedGroup`
+    pub fn new() -> LedGroup {
+        LedGroup { leds: Vec::new() }
+    }
+
+    /// Add an LED to the group
+    pub fn push(&mut self, led: BoxedLed) {
+        self.leds.push(led);
+    }
+}
+
+impl Led for LedGroup {
+    fn brightness(&self) -> Result<Brightness> {
+        match self.leds.first() {
+            Some(led) => led.brightness(),
+            None => Ok(Brightness::Off),
+        }
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        match self.leds.first() {
+            Some(led) => led.max_brightness(),
+            None => Ok(255),
+        }
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        let mut result = Ok(());
+        for led in &mut self.leds {
+            let led_result = led.set_brightness(brightness);
+            if result.is_ok() {
+                result = led_result;
+            }
+        }
+        result
+    }
+}
+
+/// Adapter that drives a single-color `SysfsLed` as an `RgbLed`
+///
+/// The brightness of the LED is set from the luminance of each color, so that
+/// code written against `RgbLed` can also run on hardware with a single LED.
+pub struct LumaLed {
+    led: SysfsLed,
+    color: Color,
+}
+
+impl LumaLed {
+    /// Create a new `LumaLed` wrapping an existing `SysfsLed`
+    ///
+    /// The stored color starts out as black, but the LED is not changed until
+    /// a color is set.
+    pub fn new(led: SysfsLed) -> LumaLed {
+        LumaLed {
+            led: led,
+            color: colors::BLACK,
+        }
+    }
+}
+
+impl Led for LumaLed {
+    fn brightness(&self) -> Result<Brightness> {
+        self.led.brightness()
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        self.led.set_brightness(brightness)
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        self.led.max_brightness()
+    }
+}
+
+impl RgbLed for LumaLed {
+    fn color(&self) -> Result<Color> {
+        Ok(self.color)
+    }
+
+    fn set_color(&mut self, color: Color) -> Result<()> {
+        let max_brightness = self.led.max_brightness()?;
+        let value = rescale(color.luminance() as u32, 255, max_brightness);
+        self.led.set_brightness(Brightness::Absolute(value))?;
+        self.color = color;
+        Ok(())
+    }
+}
+
+/// Software PWM dimming for LEDs that can only be switched on and off
+///
+/// Some LEDs, such as those wired to a plain GPIO, have a `max_brightness` of
+/// 1. `PwmLed` approximates intermediate brightness levels by toggling such an
+/// LED from a background thread on a 10ms period. `Absolute` brightness values
+/// are measured against an emulated `max_brightness` of 255.
+///
+/// The thread makes up to two sysfs writes and two wake-ups every period for
+/// as long as the `PwmLed` exists, so expect a small but constant CPU cost
+/// even while the LED is fully on or off. Dropping the `PwmLed` stops the
+/// thread and turns the LED off.
+pub struct PwmLed {
+    duty: Arc<AtomicUsize>,
+    running: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl PwmLed {
+    /// Create a new `PwmLed` taking over an existing `SysfsLed`
+    ///
+    /// The PWM thread starts immediately with the LED off.
+    pub fn new(led: SysfsLed) -> PwmLed {
+        let duty = Arc::new(AtomicUsize::new(0));
+        let running = Arc::new(AtomicBool::new(true));
+        let thread = {
+            let duty = duty.clone();
+            let running = running.clone();
+            thread::spawn(move || run_pwm(led, &duty, &running))
+        };
+        PwmLed {
+            duty: duty,
+            running: running,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Led for PwmLed {
+    fn brightness(&self) -> Result<Brightness> {
+        Ok(Brightness::Absolute(self.duty.load(Ordering::SeqCst) as u32))
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        self.duty.store(brightness.to_duty(PWM_MAX_BRIGHTNESS) as usize, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        Ok(PWM_MAX_BRIGHTNESS)
+    }
+}
+
+impl Drop for PwmLed {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A `SysfsLed` that can be driven from several threads at once
+///
+/// Clones share the same underlying device. Every `Led` operation holds a
+/// lock for its whole duration, so operations from different clones never
+/// interleave and the device always ends up at the brightness of whichever
+/// operation ran last. Default methods made of several operations, such as
+/// `toggle` or `blink`, take the lock once per step, so steps from other
+/// clones can land in between.
+#[derive(Clone)]
+pub struct SharedLed {
+    led: Arc<Mutex<SysfsLed>>,
+}
+
+impl SharedLed {
+    /// Create a new `SharedLed` taking over an existing `SysfsLed`
+    pub fn new(led: SysfsLed) -> SharedLed {
+        SharedLed { led: Arc::new(Mutex::new(led)) }
+    }
+
+    // A panic while holding the lock can't leave a `SysfsLed` in a bad state,
+    // so a poisoned lock is still safe to use
+    fn lock(&self) -> MutexGuard<'_, SysfsLed> {
+        self.led.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Led for SharedLed {
+    fn brightness(&self) -> Result<Brightness> {
+        self.lock().brightness()
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        self.lock().set_brightness(brightness)
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        SysfsLed::max_brightness(&self.lock())
+    }
+}
+
+// Body of the `PwmLed` thread. Write errors can't be reported from here, so
+// they are ignored and the write is retried on the next period.
+fn run_pwm(mut led: SysfsLed, duty: &AtomicUsize, running: &AtomicBool) {
+    while running.load(Ordering::SeqCst) {
+        let on_micros = duty.load(Ordering::SeqCst) as u64 * PWM_PERIOD_MICROS /
+                        PWM_MAX_BRIGHTNESS as u64;
+        if on_micros > 0 {
+            let _ = led.set_brightness(Brightness::Full);
+            thread::sleep(Duration::from_micros(on_micros));
+        }
+        if on_micros < PWM_PERIOD_MICROS {
+            let _ = led.set_brightness(Brightness::Off);
+            thread::sleep(Duration::from_micros(PWM_PERIOD_MICROS - on_micros));
+        }
+    }
+    let _ = led.set_brightness(Brightness::Off);
+}
+
+// Length of a `Duration` in seconds, as a float
+fn duration_secs(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1_000_000_000.0
+}
+
+// Find one channel of an RGB LED for `SysfsRgbLed::discover_in`, first by name
+// and then by the `color` attribute of LEDs sharing the prefix
+fn discover_channel(root: &Path, prefix: &str, channel: &str) -> Result<SysfsLed> {
+    let prefix = format!("{}:", prefix);
+    let named = root.join(format!("{}{}", prefix, channel));
+    if named.is_dir() {
+        return SysfsLed::from_path(named);
+    }
+    for entry in fs::read_dir(root)? {
+        let path = entry?.path();
+        let matches = path.file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with(&prefix));
+        if !matches {
+            continue;
+        }
+        if let Ok(led) = SysfsLed::from_path(&path) {
+            // An unreadable `color` on some other device just isn't a match
+            let color = led.physical_color().ok().and_then(|color| color);
+            if color.map_or(false, |color| color == channel) {
+                return Ok(led);
+            }
+        }
+    }
+    bail!(ErrorKind::MissingChannel(channel.into()))
+}
+
+// Rescale an absolute brightness from one maximum to another, rounding to the
+// nearest value
+fn rescale(value: u32, from_max: u32, to_max: u32) -> u32 {
+    if from_max == 0 {
+        return 0;
+    }
+    let value = cmp::min(value, from_max) as u64;
+    let from_max = from_max as u64;
+    ((value * to_max as u64 + from_max / 2) / from_max) as u32
+}
+
+// Read the brightness of one channel of an RGB LED, scaled to 0-255
+fn read_channel(led: &SysfsLed, max_brightness: u32) -> Result<u8> {
+    let value = led.sysfs_read_value::<u32>("brightness")?;
+    Ok(rescale(value, max_brightness, 255) as u8)
+}
+
+// Write a 0-255 channel value to one channel of an RGB LED. The value is
+// gamma encoded first if a gamma is given, then scaled to max_brightness, and
+// finally inverted for LEDs that are lit by pulling the channel low.
+fn write_scaled_channel(led: &mut SysfsLed,
+                        value: u8,
+                        max_brightness: u32,
+                        inverted: bool,
+                        gamma: Option<f32>)
+                        -> Result<()> {
+    let value = match gamma {
+        Some(gamma) => colors::gamma_channel(value, gamma),
+        None => value,
+    };
+    let mut value = rescale(value as u32, 255, max_brightness);
+    if inverted {
+        value = max_brightness - value;
+    }
+    led.paced_write(|led| led.sysfs_write_file("brightness", &format!("{}", value)))
+}
+
+// Reject attribute names that could reach outside the device directory
+fn check_attribute_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains('/') || name.contains("..") {
+        bail!(ErrorKind::InvalidAttributeName(name.into()));
+    }
+    Ok(())
+}
+
+// Make sure that the specified files exist in the given directory
+fn require_device_files<D>(dir: D) -> Result<()>
+    where D: AsRef<Path>
+{
+    for file in &["brightness", "max_brightness"] {
+        if !dir.as_ref().join(file).is_file() {
+            bail!(ErrorKind::InvalidDevicePath(dir.as_ref().to_string_lossy().into()));
+        }
+    }
+
+    Ok(())
+}
+
+fn sysfs_read_file(device_path: &Path, name: &str) -> Result<String> {
+    let path = device_path.join(name);
+    let mut file = OpenOptions::new().read(true)
+        .open(&path)
+        .map_err(|e| map_read_error(e, device_path, name))?;
+    let mut result = String::new();
+    file.read_to_string(&mut result)?;
+    #[cfg(feature = "log")]
+    log::debug!("read {:?} from {}", result.trim(), path.display());
+    Ok(result.trim().into())
+}
+
+fn sysfs_write_file(device_path: &Path, name: &str, value: &str) -> Result<()> {
+    let path = device_path.join(name);
+    #[cfg(feature = "log")]
+    log::debug!("write {:?} to {}", value, path.display());
+    let mut file = OpenOptions::new().write(true)
+        .truncate(true)
+        .create(false)
+        .open(&path)
+        .map_err(|e| map_write_error(e, &path))?;
+    // Drivers reject out-of-range values at write time, so name the value
+    file.write_all(value.as_bytes())
+        .map_err(|e| map_write_error(e, &path))
+        .chain_err(|| ErrorKind::WriteFailed(path.to_string_lossy().into(), value.into()))
+}
+
+// Name the missing attribute when a read fails because the file is gone, as
+// happens when a driver doesn't provide it or the device disappears
+fn map_read_error(error: io::Error, device_path: &Path, name: &str) -> Error {
+    match error.kind() {
+        io::ErrorKind::NotFound => {
+            ErrorKind::MissingAttribute(device_path.to_string_lossy().into(), name.into()).into()
+        }
+        _ => error.into(),
+    }
+}
+
+// Give permission errors on writes a dedicated error kind, since they usually
+// mean the process needs more privileges rather than a driver problem. Files
+// without any write bits can't be written even by root, so report those as
+// read-only instead.
+fn map_write_error(error: io::Error, path: &Path) -> Error {
+    match error.kind() {
+        io::ErrorKind::PermissionDenied => {
+            let read_only = fs::metadata(path).map(|m| m.permissions().readonly());
+            if let Ok(true) = read_only {
+                ErrorKind::ReadOnlyDevice(path.to_string_lossy().into()).into()
+            } else {
+                ErrorKind::PermissionDenied(path.to_string_lossy().into()).into()
+            }
+        }
+        _ => error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::path::Path;
+
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::testing::MockLed;
+    use crate::triggers::{TriggerBacklight, TriggerCpu, TriggerDisk, TriggerGpio, TriggerHeartbeat,
+                          TriggerNone, TriggerTimer};
+
+    struct SysfsWrapper(TempDir);
+
+    impl SysfsWrapper {
+        fn path(&self) -> &Path {
+            self.0.path()
+        }
+
+        fn get(&self, name: &str) -> String {
+            let mut result = String::new();
+            File::open(self.path().join(name))
+                .expect(&format!("opening {}", name))
+                .read_to_string(&mut result)
+                .expect(&format!("reading {}", name));
+            result
+        }
+
+        fn set(&mut self, name: &str, value: &str) {
+            File::open(self.path().join(name))
+                .expect(&format!("opening {}", name))
+                .write_all(value.as_bytes())
+                .expect(&format!("writing {}", name));
+        }
+    }
+
+    macro_rules! create_sysfs_dir {
+        ( $name:expr; $( $file:expr => $value:expr );+ ) => {{
+            let tempdir = TempDir::new($name).expect("create temp dir");
+            $({
+                let mut file = File::create(tempdir.path().join($file))
+                    .expect(concat!("create ", $file, " file"));
+                file.write_all($value.as_bytes())
+                    .expect(concat!("writing ", $file, " initial value"));
+            })+
+
+            SysfsWrapper(tempdir)
+        }};
+    }
+
+    // Create an LED class device under `root` with the given attribute files
+    // on top of `brightness` and `max_brightness`
+    fn create_class_device(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let device = root.join(name);
+        fs::create_dir(&device).expect("create device dir");
+        let defaults = [("brightness", "0"), ("max_brightness", "255")];
+        for &(file, value) in defaults.iter().chain(files) {
+            let mut file = File::create(device.join(file)).expect("create device file");
+            file.write_all(value.as_bytes()).expect("write device file");
+        }
+    }
+
+    #[test]
+    fn test_new_in() {
+        let root = TempDir::new("sysfs_led_class").expect("create temp dir");
+        for name in &["status", "red", "green", "blue"] {
+            create_class_device(root.path(), name, &[]);
+        }
+
+        let mut led = SysfsLed::new_in(root.path(), "status").expect("create sysfs led");
+        assert_eq!(root.path().join("status"), led.device_path());
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!("255", fs::read_to_string(root.path().join("status/brightness")).unwrap());
+
+        let rgb = SysfsRgbLed::new_in(root.path(), "red", "green", "blue")
+            .expect("create sysfs rgb led");
+        assert_eq!((255, 255, 255), rgb.channel_maxes());
+
+        match SysfsLed::new_in(root.path(), "missing") {
+            Err(Error(ErrorKind::InvalidDevicePath(_), _)) => {}
+            other => panic!("expected InvalidDevicePath, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_open() {
+        let root = TempDir::new("sysfs_led_class").expect("create temp dir");
+        create_class_device(root.path(), "status", &[("trigger", "[none] timer")]);
+        let led = SysfsLed::open(root.path().join("status")).expect("open sysfs led");
+        assert_eq!(root.path().join("status"), led.device_path());
+
+        for missing in &["brightness", "max_brightness", "trigger"] {
+            create_class_device(root.path(), missing, &[("trigger", "[none] timer")]);
+            let dir = root.path().join(missing);
+            fs::remove_file(dir.join(missing)).expect("remove attribute");
+            match SysfsLed::open(&dir) {
+                Err(Error(ErrorKind::MissingAttribute(ref path, ref attribute), _)) => {
+                    assert_eq!(dir.to_string_lossy(), path.as_str());
+                    assert_eq!(missing, attribute);
+                }
+                other => panic!("expected MissingAttribute, got {:?}", other.map(|_| ())),
+            }
+        }
+
+        match SysfsLed::open(root.path().join("absent")) {
+            Err(Error(ErrorKind::InvalidDevicePath(_), _)) => {}
+            other => panic!("expected InvalidDevicePath, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_discover_rgb() {
+        let root = TempDir::new("sysfs_led_class").expect("create temp dir");
+        create_class_device(root.path(), "rgb:red", &[("max_brightness", "100")]);
+        create_class_device(root.path(), "rgb:green", &[]);
+        create_class_device(root.path(), "rgb:status-2", &[("color", "blue")]);
+        create_class_device(root.path(), "other:blue", &[]);
+        create_class_device(root.path(), "other:broken", &[]);
+        fs::write(root.path().join("other:broken/color"), [0xff, 0xfe]).expect("write color");
+
+        let mut led = SysfsRgbLed::discover_in(root.path(), "rgb").expect("discover rgb led");
+        led.set_color(Color::from_rgb(255, 0, 255)).expect("set color");
+        let read = |name: &str| fs::read_to_string(root.path().join(name).join("brightness"));
+        assert_eq!("100", read("rgb:red").unwrap());
+        assert_eq!("0", read("rgb:green").unwrap());
+        assert_eq!("255", read("rgb:status-2").unwrap());
+        assert_eq!("0", read("other:blue").unwrap());
+
+        match SysfsRgbLed::discover_in(root.path(), "other") {
+            Err(Error(ErrorKind::MissingChannel(ref c), _)) if c == "red" => {}
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+
+    // Records log messages per thread, since tests run in parallel
+    #[cfg(feature = "log")]
+    struct CaptureLogger;
+
+    #[cfg(feature = "log")]
+    thread_local! {
+        static CAPTURED: ::std::cell::RefCell<Vec<String>> = ::std::cell::RefCell::new(Vec::new());
+    }
+
+    #[cfg(feature = "log")]
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let message = format!("{}", record.args());
+            CAPTURED.with(|captured| captured.borrow_mut().push(message));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_sysfs_access() {
+        static LOGGER: CaptureLogger = CaptureLogger;
+        // Only the first test to install a logger succeeds, which is fine
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(log::LevelFilter::Debug);
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "128";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        CAPTURED.with(|captured| captured.borrow_mut().clear());
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        let path = harness.path().join("brightness");
+        let expected = format!("write \"128\" to {}", path.display());
+        CAPTURED.with(|captured| assert!(captured.borrow().contains(&expected),
+                                         "{:?} not in {:?}", expected, captured.borrow()));
+        led.brightness().expect("get brightness");
+        let expected = format!("read \"128\" from {}", path.display());
+        CAPTURED.with(|captured| assert!(captured.borrow().contains(&expected),
+                                         "{:?} not in {:?}", expected, captured.borrow()));
+    }
+
+    #[test]
+    fn test_set_brightness() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "128";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let vectors = vec![(Brightness::Full, "128"),
+                           (Brightness::Percent(50), "64"),
+                           (Brightness::Percent(150), "128"),
+                           (Brightness::Absolute(0), "0"),
+                           (Brightness::Absolute(72), "72"),
+                           (Brightness::Absolute(129), "128"),
+                           (Brightness::Off, "0")];
+        for (brightness, expected) in vectors {
+            led.set_brightness(brightness).expect(&format!("setting brightness={:?}", brightness));
+            assert_eq!(expected, harness.get("brightness"));
+        }
+    }
+
+    #[test]
+    fn test_is_on() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert!(!led.is_on().expect("check led"));
+        led.set_brightness(Brightness::Absolute(3)).expect("set brightness");
+        assert!(led.is_on().expect("check led"));
+        led.set_brightness(Brightness::Off).expect("set brightness");
+        assert!(!led.is_on().expect("check led"));
+    }
+
+    #[test]
+    fn test_ease_in() {
+        let mut led = MockLed::new();
+        led.ease(Brightness::Absolute(0),
+                  Brightness::Absolute(200),
+                  Duration::from_millis(8),
+                  8,
+                  Easing::EaseInQuad)
+            .expect("ease");
+
+        let values: Vec<u32> = led.brightness_history()
+            .iter()
+            .map(|brightness| brightness.to_absolute(255))
+            .collect();
+        assert_eq!(8, values.len());
+        assert_eq!(Some(&200), values.last());
+        let deltas: Vec<u32> = values.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(deltas.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", values);
+    }
+
+    #[test]
+    fn test_ease_mixed_variants() {
+        let mut led = MockLed::with_max_brightness(1000);
+        led.ease(Brightness::Absolute(500),
+                  Brightness::Full,
+                  Duration::from_millis(0),
+                  10,
+                  Easing::Linear)
+            .expect("ease");
+        let values: Vec<u32> = led.brightness_history()
+            .iter()
+            .map(|brightness| brightness.to_absolute(1000))
+            .collect();
+        assert!(values[0] >= 500 && values[0] <= 600, "{:?}", values);
+        assert_eq!(Some(&1000), values.last());
+    }
+
+    #[test]
+    fn test_ease_zero_steps() {
+        let mut led = MockLed::new();
+        led.ease(Brightness::Off, Brightness::Full, Duration::from_secs(1), 0, Easing::Linear)
+            .expect("ease");
+        assert_eq!(&[Brightness::Full], led.brightness_history());
+    }
+
+    #[test]
+    fn test_ease_many_steps() {
+        let mut led = MockLed::new();
+        led.ease(Brightness::Off, Brightness::Full, Duration::from_secs(0), 100_000, Easing::Linear)
+            .expect("ease");
+        assert_eq!(100_000, led.brightness_history().len());
+        assert_eq!(Some(255), led.last_brightness().map(|b| b.to_absolute(255)));
+
+        // Stops after the first write, so `u32::MAX` steps finish quickly
+        struct StopLed(Vec<Brightness>);
+
+        impl Led for StopLed {
+            fn brightness(&self) -> Result<Brightness> {
+                Ok(Brightness::Off)
+            }
+
+            fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+                self.0.push(brightness);
+                bail!("stop")
+            }
+        }
+
+        let mut led = StopLed(vec![]);
+        assert!(led.ease(Brightness::Off,
+                      Brightness::Full,
+                      Duration::from_secs(0),
+                      u32::max_value(),
+                      Easing::Linear)
+            .is_err());
+        assert_eq!(1, led.0.len());
+    }
+
+    #[test]
+    fn test_pulse_between() {
+        let mut led = MockLed::new();
+        led.set_brightness(Brightness::Absolute(42)).expect("set brightness");
+        let cancel = Arc::new(AtomicBool::new(false));
+        let canceller = {
+            let cancel = cancel.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(100));
+                cancel.store(true, Ordering::SeqCst);
+            })
+        };
+        led.pulse_between(Brightness::Absolute(10),
+                           Brightness::Absolute(200),
+                           Duration::from_millis(40),
+                           &cancel)
+            .expect("pulse");
+        canceller.join().expect("join canceller");
+
+        assert_eq!(Some(Brightness::Absolute(42)), led.last_brightness());
+        assert_eq!(Brightness::Absolute(42), led.brightness().expect("get brightness"));
+        let history = led.brightness_history();
+        let values: Vec<u32> = history[1..history.len() - 1]
+            .iter()
+            .map(|brightness| brightness.to_absolute(255))
+            .collect();
+        assert!(values.iter().all(|&value| value >= 10 && value <= 200));
+        assert!(values.iter().any(|&value| value < 50));
+        assert!(values.iter().any(|&value| value > 160));
+    }
+
+    #[test]
+    fn test_rescale() {
+        assert_eq!(0, rescale(0, 128, 255));
+        assert_eq!(255, rescale(128, 128, 255));
+        assert_eq!(128, rescale(64, 128, 255));
+        assert_eq!(64, rescale(128, 255, 128));
+        assert_eq!(255, rescale(300, 128, 255));
+        assert_eq!(0, rescale(10, 0, 255));
+    }
+
+    #[test]
+    fn test_percent() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "200";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        for &(percent, written) in &[(0, "0"), (25, "50"), (50, "100"), (100, "200")] {
+            led.set_percent(percent).expect("set percent");
+            assert_eq!(written, harness.get("brightness"));
+            assert_eq!(percent, led.percent().expect("get percent"));
+        }
+        led.set_percent(150).expect("set percent");
+        assert_eq!("200", harness.get("brightness"));
+        assert_eq!(100, led.percent().expect("get percent"));
+
+        assert_eq!(100, Brightness::Full.to_percent(200));
+        assert_eq!(0, Brightness::Absolute(10).to_percent(0));
+    }
+
+    #[test]
+    fn test_toggle() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "128";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.toggle().expect("toggle on");
+        assert_eq!("128", harness.get("brightness"));
+        led.toggle().expect("toggle off");
+        assert_eq!("0", harness.get("brightness"));
+
+        led.set_brightness(Brightness::Absolute(1)).expect("set brightness");
+        led.toggle().expect("toggle off");
+        assert_eq!("0", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_brightness_above_max() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "300";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Brightness::Absolute(255), led.brightness().expect("get brightness"));
+    }
+
+    #[test]
+    fn test_invalid_file_contents() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "abc";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        match led.max_brightness() {
+            Err(Error(ErrorKind::InvalidFileContents(ref file, ref contents), _)) => {
+                assert_eq!("max_brightness", file);
+                assert_eq!("abc", contents);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        let error = led.brightness().expect_err("read brightness");
+        assert!(error.to_string().contains("max_brightness"));
+    }
+
+    #[test]
+    fn test_map_write_error() {
+        let path = Path::new("/sys/class/leds/led0/brightness");
+        let error = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        match map_write_error(error, path) {
+            Error(ErrorKind::PermissionDenied(ref p), _) => {
+                assert_eq!("/sys/class/leds/led0/brightness", p)
+            }
+            error => panic!("unexpected error: {:?}", error),
+        }
+
+        let error = io::Error::new(io::ErrorKind::NotFound, "not found");
+        match map_write_error(error, path) {
+            Error(ErrorKind::Io(_), _) => {}
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_read_only_brightness() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert!(led.is_writable().expect("check writable"));
+
+        let path = harness.path().join("brightness");
+        let mut permissions = fs::metadata(&path).expect("read metadata").permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).expect("set permissions");
+        assert!(!led.is_writable().expect("check writable"));
+
+        // Root can write read-only files outside sysfs, so fake the kernel's
+        // EACCES rather than relying on the write failing
+        let error = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        match map_write_error(error, &path) {
+            Error(ErrorKind::ReadOnlyDevice(ref p), _) => assert_eq!(&path.to_string_lossy(), p),
+            error => panic!("unexpected error: {:?}", error),
+        }
+    }
+
+    #[test]
+    fn test_write_error_value() {
+        // Every write to /dev/full fails, like a driver rejecting a value
+        match sysfs_write_file(Path::new("/dev"), "full", "300") {
+            Err(error @ Error(ErrorKind::WriteFailed(..), _)) => {
+                assert_eq!("failed to write '300' to '/dev/full'", error.to_string());
+                assert!(error.iter().nth(1).is_some());
+            }
+            other => panic!("expected WriteFailed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_attributes() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]";
+                                        "brightness_hw_changed" => "17\n");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!("17", led.read_attribute("brightness_hw_changed").expect("read attribute"));
+        led.write_attribute("brightness_hw_changed", "42").expect("write attribute");
+        assert_eq!("42", harness.get("brightness_hw_changed"));
+
+        for name in &["../foo", "..", "device/uevent", "/etc/passwd", ""] {
+            match led.read_attribute(name) {
+                Err(Error(ErrorKind::InvalidAttributeName(_), _)) => {}
+                other => panic!("expected InvalidAttributeName for {:?}, got {:?}", name, other),
+            }
+            match led.write_attribute(name, "1") {
+                Err(Error(ErrorKind::InvalidAttributeName(_), _)) => {}
+                other => panic!("expected InvalidAttributeName for {:?}, got {:?}", name, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_max_brightness() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "0";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path())
+            .expect("create sysfs led")
+            .with_max_brightness(200);
+        assert_eq!(200, led.max_brightness().expect("read max brightness"));
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!("100", harness.get("brightness"));
+        assert_eq!("0", harness.get("max_brightness"));
+
+        // Resuming only clears the cache, not the override
+        let state = led.snapshot().expect("snapshot state");
+        led.resume_from(&state).expect("resume from state");
+        assert_eq!(200, led.max_brightness().expect("read max brightness"));
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!("200", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_device_path() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(harness.path(), led.device_path());
+    }
+
+    #[test]
+    fn test_canonical_path() {
+        use std::os::unix::fs::symlink;
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let class_dir = TempDir::new("sysfs_led_class").expect("create class dir");
+        let link = class_dir.path().join("led0");
+        symlink(harness.path(), &link).expect("symlink led dir");
+        let led = SysfsLed::from_path(&link).expect("create sysfs led");
+        assert_eq!(fs::canonicalize(harness.path()).expect("canonicalize harness"),
+                   led.canonical_path().expect("get canonical path"));
+    }
+
+    #[test]
+    fn test_uevent() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert!(led.uevent().expect("read missing uevent").is_empty());
+
+        fs::create_dir(harness.path().join("device")).expect("create device dir");
+        File::create(harness.path().join("device/uevent"))
+            .and_then(|mut f| f.write_all(b"DRIVER=leds-gpio\nOF_NAME=leds\nMODALIAS=of:Nleds\n"))
+            .expect("create uevent file");
+        let uevent = led.uevent().expect("read uevent");
+        assert_eq!(3, uevent.len());
+        assert_eq!("leds-gpio", uevent["DRIVER"]);
+        assert_eq!("leds", uevent["OF_NAME"]);
+        assert_eq!("of:Nleds", uevent["MODALIAS"]);
+    }
+
+    #[test]
+    fn test_physical_color() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(None, led.physical_color().expect("read missing color"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "color" => "amber\n");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Some("amber".into()), led.physical_color().expect("read color"));
+    }
+
+    #[test]
+    fn test_no_trigger_file() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "1");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!("1", harness.get("brightness"));
+        assert_eq!(Trigger::None, led.active_trigger().expect("read active trigger"));
+        match led.none() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "none" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_active_trigger() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none timer [heartbeat] cpu0";
+                                        "invert" => "1");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Trigger::Heartbeat { invert: true },
+                   led.active_trigger().expect("read active trigger"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none [timer] heartbeat cpu0";
+                                        "delay_on" => "100";
+                                        "delay_off" => "900");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Trigger::Timer { on: 100, off: 900 },
+                   led.active_trigger().expect("read active trigger"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none timer heartbeat [cpu1] mmc0");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Trigger::Cpu(1), led.active_trigger().expect("read active trigger"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none timer heartbeat cpu1 [mmc0]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Trigger::Other("mmc0".into()),
+                   led.active_trigger().expect("read active trigger"));
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer heartbeat";
+                                        "delay_on" => "0";
+                                        "delay_off" => "0");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let state = LedState {
+            brightness: Brightness::Absolute(42),
+            trigger: Trigger::None,
+        };
+        led.restore(&state).expect("restore state");
+        assert_eq!("none", harness.get("trigger"));
+        assert_eq!("42", harness.get("brightness"));
+
+        let state = LedState {
+            brightness: Brightness::Absolute(0),
+            trigger: Trigger::Timer { on: 100, off: 900 },
+        };
+        led.restore(&state).expect("restore state");
+        assert_eq!("timer", harness.get("trigger"));
+        assert_eq!("100", harness.get("delay_on"));
+        assert_eq!("900", harness.get("delay_off"));
+        assert_eq!("42", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_resume_from() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "42";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer heartbeat");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let state = led.snapshot().expect("snapshot state");
+        assert_eq!(Brightness::Absolute(42), state.brightness);
+        assert_eq!(Trigger::None, state.trigger);
+
+        // Simulate the device disappearing across suspend
+        fs::remove_dir_all(harness.path()).expect("remove device dir");
+        assert!(led.resume_from(&state).is_err());
+
+        // Simulate the device being re-enumerated with default values
+        fs::create_dir(harness.path()).expect("recreate device dir");
+        for &(file, value) in &[("brightness", "0"),
+                                ("max_brightness", "255"),
+                                ("trigger", "none [heartbeat] timer")] {
+            File::create(harness.path().join(file))
+                .and_then(|mut f| f.write_all(value.as_bytes()))
+                .expect("recreate device file");
+        }
+        led.resume_from(&state).expect("resume from state");
+        assert_eq!("none", harness.get("trigger"));
+        assert_eq!("42", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_set_max_brightness() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(255, led.max_brightness().expect("read max brightness"));
+
+        led.set_max_brightness(100).expect("set max brightness");
+        assert_eq!("100", harness.get("max_brightness"));
+        assert_eq!(100, led.max_brightness().expect("read max brightness"));
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!("100", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_mirror_to() {
+        let source = create_sysfs_dir!("sysfs_led_test";
+                                       "brightness" => "64";
+                                       "max_brightness" => "128";
+                                       "trigger" => "[none]");
+        let dest = create_sysfs_dir!("sysfs_led_test";
+                                     "brightness" => "0";
+                                     "max_brightness" => "255";
+                                     "trigger" => "[none]");
+        let source_led = SysfsLed::from_path(source.path()).expect("create source led");
+        let mut dest_led = SysfsLed::from_path(dest.path()).expect("create dest led");
+        source_led.mirror_to(&mut dest_led).expect("mirror brightness");
+        assert_eq!("128", dest.get("brightness"));
+        dest_led.mirror_to(&mut SysfsLed::from_path(source.path()).unwrap())
+            .expect("mirror brightness back");
+        assert_eq!("64", source.get("brightness"));
+    }
+
+    #[test]
+    fn test_reset_trigger() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none [timer] heartbeat";
+                                        "delay_on" => "100";
+                                        "delay_off" => "900");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.reset_trigger().expect("reset trigger");
+        assert_eq!("none", harness.get("trigger"));
+        assert_eq!("0", harness.get("delay_on"));
+        assert_eq!("0", harness.get("delay_off"));
+        assert!(!harness.path().join("invert").exists());
+    }
+
+    #[test]
+    fn test_disk_and_backlight_triggers() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] disk-activity disk-read disk-write");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        // The harness file only holds the last write, so restore the list
+        let reset = || {
+            fs::write(harness.path().join("trigger"), "[none] disk-activity disk-read disk-write")
+                .expect("write trigger")
+        };
+        led.disk_activity().expect("set disk-activity trigger");
+        assert_eq!("disk-activity", harness.get("trigger"));
+        reset();
+        led.disk_read().expect("set disk-read trigger");
+        assert_eq!("disk-read", harness.get("trigger"));
+        reset();
+        led.disk_write().expect("set disk-write trigger");
+        assert_eq!("disk-write", harness.get("trigger"));
+        reset();
+        match led.backlight() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "backlight" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!("[none] disk-activity disk-read disk-write", harness.get("trigger"));
+
+        fs::write(harness.path().join("trigger"), "[none] backlight").expect("write trigger");
+        led.backlight().expect("set backlight trigger");
+        assert_eq!("backlight", harness.get("trigger"));
+        match led.disk_activity() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "disk-activity" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        match led.disk_activity() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "disk-activity" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match led.backlight() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "backlight" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_cpu_triggers() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] cpu cpu0 cpu1 activity");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.cpu_all().expect("set cpu trigger");
+        assert_eq!("cpu", harness.get("trigger"));
+
+        fs::write(harness.path().join("trigger"), "[none] cpu cpu0 cpu1 activity")
+            .expect("write trigger");
+        led.activity().expect("set activity trigger");
+        assert_eq!("activity", harness.get("trigger"));
+
+        fs::write(harness.path().join("trigger"), "[none] cpu0 cpu1").expect("write trigger");
+        match led.cpu_all() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "cpu" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        match led.activity() {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "activity" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!("[none] cpu0 cpu1", harness.get("trigger"));
+    }
+
+    #[test]
+    fn test_gpio_trigger() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer gpio";
+                                        "gpio" => "0";
+                                        "inverted" => "0";
+                                        "desired_brightness" => "0");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.gpio(17, true, 128).expect("set gpio trigger");
+        assert_eq!("gpio", harness.get("trigger"));
+        assert_eq!("17", harness.get("gpio"));
+        assert_eq!("1", harness.get("inverted"));
+        assert_eq!("128", harness.get("desired_brightness"));
+
+        led.gpio(4, false, 1000).expect("set gpio trigger");
+        assert_eq!("4", harness.get("gpio"));
+        assert_eq!("0", harness.get("inverted"));
+        assert_eq!("255", harness.get("desired_brightness"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        match led.gpio(17, false, 255) {
+            Err(Error(ErrorKind::UnsupportedTrigger(ref t), _)) if t == "gpio" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_current_timings() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none [timer] heartbeat";
+                                        "delay_on" => "250";
+                                        "delay_off" => "750");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!((250, 750), led.current_timings().expect("read timings"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer heartbeat");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        match led.current_timings() {
+            Err(Error(ErrorKind::TriggerNotActive(ref t), _)) if t == "timer" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_timer_delays() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none [timer] heartbeat";
+                                        "delay_on" => "100";
+                                        "delay_off" => "900");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!((100, 900), led.timer_delays().expect("read delays"));
+        led.timer(300, 700).expect("set timer");
+        assert_eq!((300, 700), led.timer_delays().expect("read delays"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer heartbeat");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        match led.timer_delays() {
+            Err(Error(ErrorKind::TriggerNotActive(ref t), _)) if t == "timer" => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_led_array_set_all() {
+        let harnesses = (0..8)
+            .map(|_| {
+                create_sysfs_dir!("sysfs_led_test";
+                                  "brightness" => "0";
+                                  "max_brightness" => "128";
+                                  "trigger" => "[none]")
+            })
+            .collect::<Vec<_>>();
+        let leds = harnesses.iter()
+            .map(|h| SysfsLed::from_path(h.path()).expect("create sysfs led"))
+            .collect();
+        let mut array = LedArray::new(leds);
+
+        array.set_all(Brightness::Percent(50)).expect("set all");
+        for harness in &harnesses {
+            assert_eq!("64", harness.get("brightness"));
+        }
+        array.set_all(Brightness::Off).expect("set all");
+        for harness in &harnesses {
+            assert_eq!("0", harness.get("brightness"));
+        }
+    }
+
+    #[test]
+    fn test_led_pool() {
+        let harnesses = (0..4)
+            .map(|_| {
+                create_sysfs_dir!("sysfs_led_test";
+                                  "brightness" => "0";
+                                  "max_brightness" => "128";
+                                  "trigger" => "[none]")
+            })
+            .collect::<Vec<_>>();
+        let leds = harnesses.iter()
+            .map(|h| SysfsLed::from_path(h.path()).expect("create sysfs led"))
+            .collect();
+        let mut pool = LedPool::new(leds).expect("create led pool");
+        assert_eq!(4, pool.len());
+
+        // Sysfs takes each write whole, but a regular file keeps the tail of
+        // a longer earlier value, so only compare what was last written
+        let written = |index: usize, expected: &str| {
+            let contents = harnesses[index].get("brightness");
+            assert!(contents.starts_with(expected), "{} is not {}", contents, expected);
+        };
+        pool.set_all(Brightness::Full).expect("set all");
+        pool.set(1, Brightness::Percent(50)).expect("set brightness");
+        pool.set(3, Brightness::Off).expect("set brightness");
+        for (index, expected) in ["128", "64", "128", "0"].iter().enumerate() {
+            written(index, expected);
+        }
+
+        pool.set(0, Brightness::Absolute(7)).expect("set brightness");
+        written(0, "7");
+    }
+
+    #[test]
+    fn test_led_group() {
+        let first = create_sysfs_dir!("sysfs_led_test";
+                                      "brightness" => "0";
+                                      "max_brightness" => "255";
+                                      "trigger" => "[none]");
+        let second = create_sysfs_dir!("sysfs_led_test";
+                                       "brightness" => "0";
+                                       "max_brightness" => "128";
+                                       "trigger" => "[none]");
+        let mut group = LedGroup::new();
+        assert_eq!(Brightness::Off, group.brightness().expect("get brightness"));
+        group.push(Box::new(SysfsLed::from_path(first.path()).expect("create first led")));
+        group.push(Box::new(SysfsLed::from_path(second.path()).expect("create second led")));
+
+        group.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!("127", first.get("brightness"));
+        assert_eq!("64", second.get("brightness"));
+        assert_eq!(Brightness::Absolute(127), group.brightness().expect("get brightness"));
+    }
+
+    #[test]
+    fn test_luma_led() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let mut luma = LumaLed::new(led);
+        luma.set_color(colors::GREEN).expect("set color");
+        assert_eq!("182", harness.get("brightness"));
+        assert_eq!(colors::GREEN, luma.color().expect("get color"));
+        luma.set_color(colors::WHITE).expect("set color");
+        assert_eq!("255", harness.get("brightness"));
+        assert_eq!(colors::WHITE, luma.color().expect("get color"));
+    }
+
+    macro_rules! create_rgb_harness {
+        ( $( $channel:expr => $value:expr, $max:expr );+ ) => {
+            vec![$(create_sysfs_dir!($channel;
+                                     "brightness" => $value;
+                                     "max_brightness" => $max;
+                                     "trigger" => "[none]")),+]
+        };
+    }
+
+    #[test]
+    fn test_set_color() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "128";
+                                          "blue" => "0", "1023");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        led.set_color(Color::from_rgb(255, 128, 64)).expect("set color");
+        assert_eq!("255", harness[0].get("brightness"));
+        assert_eq!("64", harness[1].get("brightness"));
+        assert_eq!("257", harness[2].get("brightness"));
+        assert_eq!(Color::from_rgb(255, 128, 64), led.color().expect("get color"));
+    }
+
+    #[test]
+    fn test_rgb_builder() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "128";
+                                          "blue" => "0", "1023");
+        let open = |i: usize| SysfsLed::from_path(harness[i].path()).expect("create sysfs led");
+        let mut led = SysfsRgbLed::builder()
+            .red(open(0))
+            .green(open(1))
+            .blue(open(2))
+            .build()
+            .expect("build sysfs rgb led");
+        assert_eq!((255, 128, 1023), led.maxes);
+
+        match SysfsRgbLed::builder().red(open(0)).blue(open(2)).build() {
+            Err(Error(ErrorKind::MissingChannel(ref c), _)) if c == "green" => {}
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("built without green channel"),
+        }
+
+        let mismatched = SysfsRgbLed::builder()
+            .red(open(0))
+            .green(open(1))
+            .blue(open(2))
+            .max_ratio(4)
+            .build();
+        match mismatched {
+            Err(Error(ErrorKind::MismatchedChannels(255, 128, 1023), _)) => {}
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(_) => panic!("built with mismatched channels"),
+        }
+
+        // Cached maxes are reused, so the files are not needed after building
+        for h in &harness {
+            fs::remove_file(h.path().join("max_brightness")).expect("remove max_brightness");
+        }
+        led.set_color(Color::from_rgb(255, 128, 64)).expect("set color");
+        assert_eq!("255", harness[0].get("brightness"));
+        assert_eq!("64", harness[1].get("brightness"));
+        assert_eq!("257", harness[2].get("brightness"));
+        assert_eq!(Color::from_rgb(255, 128, 64), led.color().expect("get color"));
+    }
+
+    #[test]
+    fn test_write_scaled_channel() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "1000");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let vectors = [(128, false, None, "502"),
+                       (128, true, None, "498"),
+                       (128, false, Some(2.0), "251"),
+                       (128, true, Some(2.0), "749"),
+                       (255, false, Some(2.2), "1000"),
+                       (255, true, Some(2.2), "0"),
+                       (0, false, None, "0"),
+                       (0, true, Some(2.2), "1000")];
+        for &(value, inverted, gamma, expected) in &vectors {
+            write_scaled_channel(&mut led, value, 1000, inverted, gamma).expect("write channel");
+            assert_eq!(expected, harness.get("brightness"),
+                       "value {} inverted {} gamma {:?}", value, inverted, gamma);
+        }
+    }
+
+    #[test]
+    fn test_channel_maxes_cached() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "128";
+                                          "blue" => "0", "1023");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        assert_eq!((255, 128, 1023), led.channel_maxes());
+
+        // Any further read of max_brightness would now fail
+        for channel in &harness {
+            fs::remove_file(channel.path().join("max_brightness")).expect("remove max_brightness");
+        }
+        led.set_color(Color::from_rgb(255, 128, 64)).expect("set color");
+        assert_eq!("255", harness[0].get("brightness"));
+        assert_eq!("64", harness[1].get("brightness"));
+        assert_eq!("257", harness[2].get("brightness"));
+        assert_eq!(Color::from_rgb(255, 128, 64), led.color().expect("get color"));
+    }
+
+    #[test]
+    fn test_hsv_hsl_readback() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "255";
+                                          "blue" => "0", "255");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        led.set_color_hsv(64, 192, 200).expect("set color");
+        let (hue, saturation, value) = led.hsv().expect("read hsv");
+        assert!((hue as i32 - 64).abs() <= 2, "hue {}", hue);
+        assert!((saturation as i32 - 192).abs() <= 2, "saturation {}", saturation);
+        assert!((value as i32 - 200).abs() <= 2, "value {}", value);
+
+        led.set_color(colors::RED).expect("set color");
+        assert_eq!((0, 255, 255), led.hsv().expect("read hsv"));
+        assert_eq!((0, 255, 127), led.hsl().expect("read hsl"));
+    }
+
+    #[test]
+    fn test_effective_color() {
+        let harness = create_rgb_harness!("red" => "0", "7";
+                                          "green" => "0", "255";
+                                          "blue" => "0", "1023");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        led.set_color(Color::from_rgb(100, 100, 100)).expect("set color");
+        assert_eq!(Color::from_rgb(100, 100, 100), led.color().expect("get color"));
+        assert_eq!(Color::from_rgb(109, 100, 100),
+                   led.effective_color().expect("get effective color"));
+
+        led.set_color(colors::WHITE).expect("set color");
+        assert_eq!(colors::WHITE, led.effective_color().expect("get effective color"));
+    }
+
+    #[test]
+    fn test_rgb_channels() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "128";
+                                          "blue" => "0", "1023");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        let names: Vec<String> = led.channels()
+            .map(|channel| {
+                channel.device_path().file_name().expect("file name").to_string_lossy().into_owned()
+            })
+            .collect();
+        assert_eq!(3, names.len());
+        for (name, prefix) in names.iter().zip(&["red", "green", "blue"]) {
+            assert!(name.starts_with(prefix), "{} is not {}", name, prefix);
+        }
+
+        for channel in led.channels_mut() {
+            channel.set_brightness(Brightness::Full).expect("set brightness");
+        }
+        assert_eq!("255", harness[0].get("brightness"));
+        assert_eq!("128", harness[1].get("brightness"));
+        assert_eq!("1023", harness[2].get("brightness"));
+    }
+
+    #[test]
+    fn test_rgb_set_channel() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "255";
+                                          "blue" => "0", "1023");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        led.set_channel(Channel::Blue, Brightness::Percent(50)).expect("set blue");
+        assert_eq!("511", harness[2].get("brightness"));
+        assert_eq!("0", harness[0].get("brightness"));
+        assert_eq!("0", harness[1].get("brightness"));
+        assert_eq!(Brightness::Absolute(511),
+                   led.channel_brightness(Channel::Blue).expect("get blue"));
+        assert_eq!(Brightness::Absolute(0),
+                   led.channel_brightness(Channel::Red).expect("get red"));
+
+        led.set_channel(Channel::Blue, Brightness::Off).expect("set blue");
+        assert_eq!(Brightness::Absolute(0),
+                   led.channel_brightness(Channel::Blue).expect("get blue"));
+    }
+
+    #[test]
+    fn test_rgb_brightness() {
+        let harness = create_rgb_harness!("red" => "200", "200";
+                                          "green" => "0", "255";
+                                          "blue" => "0", "255");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        assert_eq!(colors::RED, led.color().expect("get color"));
+        assert_eq!(Brightness::Full, led.brightness().expect("get brightness"));
+
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!("100", harness[0].get("brightness"));
+        led.set_color(colors::BLUE).expect("set color");
+        assert_eq!("0", harness[0].get("brightness"));
+        assert_eq!("127", harness[2].get("brightness"));
+        led.set_color(colors::RED).expect("set color");
+        assert_eq!("100", harness[0].get("brightness"));
+        assert_eq!("0", harness[2].get("brightness"));
+        assert_eq!(colors::RED, led.color().expect("get color"));
+        assert_eq!(Brightness::Percent(50), led.brightness().expect("get brightness"));
+
+        led.set_color_raw(colors::RED).expect("set raw color");
+        assert_eq!("200", harness[0].get("brightness"));
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!("200", harness[0].get("brightness"));
+        led.set_brightness(Brightness::Off).expect("set brightness");
+        assert_eq!("0", harness[0].get("brightness"));
+    }
+
+    #[test]
+    fn test_rgb_master_brightness() {
+        let root = TempDir::new("sysfs_led_class").expect("create temp dir");
+        for name in &["rgb:red", "rgb:green", "rgb:blue"] {
+            create_class_device(root.path(), name, &[("max_brightness", "200")]);
+        }
+        create_class_device(root.path(), "rgb", &[("max_brightness", "100")]);
+        let read = |name: &str| {
+            fs::read_to_string(root.path().join(name).join("brightness")).expect("read brightness")
+        };
+
+        // The bare `rgb` device next to the channels is detected as the master
+        let mut led = SysfsRgbLed::discover_in(root.path(), "rgb").expect("discover rgb led");
+        assert_eq!(100, led.max_brightness().expect("get max brightness"));
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!("50", read("rgb"));
+        led.set_color(colors::RED).expect("set color");
+        assert_eq!("200", read("rgb:red"));
+        assert_eq!(Brightness::Percent(50), led.brightness().expect("get brightness"));
+
+        let mut led = SysfsRgbLed::new_in(root.path(), "rgb:red", "rgb:green", "rgb:blue")
+            .expect("create sysfs rgb led");
+        assert_eq!(255, led.max_brightness().expect("get max brightness"));
+        fs::write(root.path().join("rgb/brightness"), "77").expect("write brightness");
+        led.set_brightness(Brightness::Percent(100)).expect("set brightness");
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!("77", read("rgb"));
+        led.set_color(colors::RED).expect("set color");
+        assert_eq!("100", read("rgb:red"));
+    }
+
+    #[test]
+    fn test_multi_led() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "255";
+                                        "max_brightness" => "255";
+                                        "multi_index" => "green red blue\n";
+                                        "multi_intensity" => "10 20 30\n");
+        let mut led = SysfsMultiLed::from_path(harness.path()).expect("create multicolor led");
+        assert_eq!(Color::from_rgb(20, 10, 30), led.color().expect("get color"));
+        led.set_color(Color::from_rgb(255, 128, 0)).expect("set color");
+        assert_eq!("128 255 0", harness.get("multi_intensity"));
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!("127", harness.get("brightness"));
+
+        led.set_min_dwell(Some(Dwell::Skip(Duration::from_secs(60))));
+        match led.set_color(colors::BLUE) {
+            Err(Error(ErrorKind::DwellNotElapsed(_), _)) => {}
+            other => panic!("expected DwellNotElapsed, got {:?}", other),
+        }
+        assert_eq!("128 255 0", harness.get("multi_intensity"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "multi_index" => "red green";
+                                        "multi_intensity" => "0 0");
+        match SysfsMultiLed::from_path(harness.path()) {
+            Err(Error(ErrorKind::MissingChannel(ref channel), _)) if channel == "blue" => {}
+            other => panic!("expected MissingChannel, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_rgb_triggers() {
+        let harness: Vec<_> = (0..3)
+            .map(|_| {
+                create_sysfs_dir!("sysfs_led_test";
+                                  "brightness" => "0";
+                                  "max_brightness" => "255";
+                                  "trigger" => "[none] timer heartbeat";
+                                  "delay_on" => "0";
+                                  "delay_off" => "0";
+                                  "invert" => "0")
+            })
+            .collect();
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+
+        led.timer(100, 900).expect("set timer trigger");
+        for channel in &harness {
+            assert_eq!("timer", channel.get("trigger"));
+            assert_eq!("100", channel.get("delay_on"));
+            assert_eq!("900", channel.get("delay_off"));
+        }
+
+        led.heartbeat(true).expect("set heartbeat trigger");
+        for channel in &harness {
+            assert_eq!("heartbeat", channel.get("trigger"));
+            assert_eq!("1", channel.get("invert"));
+        }
+
+        led.set_trigger(&Trigger::Timer { on: 50, off: 50 }).expect("set trigger");
+        for channel in &harness {
+            assert_eq!("timer", channel.get("trigger"));
+            assert_eq!("50", channel.get("delay_on"));
+        }
+    }
+
+    #[test]
+    fn test_reset() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "255";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none [timer]";
+                                        "delay_on" => "100";
+                                        "delay_off" => "900");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.reset().expect("reset led");
+        assert_eq!("none", harness.get("trigger"));
+        assert_eq!("0", harness.get("brightness"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "1";
+                                        "max_brightness" => "1");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.reset().expect("reset led without trigger");
+        assert_eq!("0", harness.get("brightness"));
+
+        let harness = create_rgb_harness!("red" => "255", "255";
+                                          "green" => "128", "255";
+                                          "blue" => "64", "255");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        led.reset().expect("reset rgb led");
+        for channel in &harness {
+            assert_eq!("none", channel.get("trigger"));
+            assert_eq!("0", channel.get("brightness"));
+        }
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!(colors::BLACK, led.color().expect("get color"));
+        assert_eq!("0", harness[0].get("brightness"));
+
+        // The brightness level is reset too, so later colors aren't dimmed
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        led.reset().expect("reset rgb led");
+        assert_eq!(Brightness::Full, led.brightness().expect("get brightness"));
+        led.set_color(colors::RED).expect("set color");
+        assert_eq!("255", harness[0].get("brightness"));
+
+        let root = TempDir::new("sysfs_led_class").expect("create temp dir");
+        for name in &["rgb:red", "rgb:green", "rgb:blue"] {
+            create_class_device(root.path(), name, &[("trigger", "[none] timer")]);
+        }
+        create_class_device(root.path(), "rgb", &[("max_brightness", "100"),
+                                                   ("trigger", "none [timer]"),
+                                                   ("delay_on", "100"),
+                                                   ("delay_off", "900")]);
+        let mut led = SysfsRgbLed::discover_in(root.path(), "rgb").expect("discover rgb led");
+        led.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        led.reset().expect("reset rgb led");
+        let read = |file: &str| fs::read_to_string(root.path().join("rgb").join(file)).unwrap();
+        assert_eq!("none", read("trigger"));
+        assert_eq!("100", read("brightness"));
+        assert_eq!(Brightness::Full, led.brightness().expect("get brightness"));
+    }
+
+    #[test]
+    fn test_dyn_max_brightness() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "200";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let rgb_harness = create_rgb_harness!("red" => "0", "100";
+                                              "green" => "0", "100";
+                                              "blue" => "0", "100");
+        let rgb = SysfsRgbLed::from_path(rgb_harness[0].path(),
+                                         rgb_harness[1].path(),
+                                         rgb_harness[2].path())
+            .expect("create sysfs rgb led");
+        let leds: Vec<&dyn Led> = vec![&led, &rgb];
+        let maxes: Vec<u32> = leds.iter()
+            .map(|led| led.max_brightness().expect("read max brightness"))
+            .collect();
+        assert_eq!(vec![200, 255], maxes);
+    }
+
+    #[test]
+    fn test_boxed_leds() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "200";
+                                        "max_brightness" => "200";
+                                        "trigger" => "[none]");
+        let rgb_harness = create_rgb_harness!("red" => "100", "100";
+                                              "green" => "100", "100";
+                                              "blue" => "100", "100");
+        let mut leds: Vec<BoxedLed> = vec![
+            Box::new(SysfsLed::from_path(harness.path()).expect("create sysfs led")),
+            Box::new(SysfsRgbLed::from_path(rgb_harness[0].path(),
+                                            rgb_harness[1].path(),
+                                            rgb_harness[2].path())
+                .expect("create sysfs rgb led")),
+            Box::new(MockLed::new()),
+        ];
+        leds[2].set_brightness(Brightness::Full).expect("set brightness");
+        assert!(leds.iter().all(|led| led.is_on().expect("check on")));
+
+        for led in &mut leds {
+            led.set_brightness(Brightness::Off).expect("set brightness");
+        }
+        assert_eq!("0", harness.get("brightness"));
+        for channel in &rgb_harness {
+            assert_eq!("0", channel.get("brightness"));
+        }
+        assert!(!leds.iter().any(|led| led.is_on().expect("check on")));
+    }
+
+    #[test]
+    fn test_set_color_hsv_hsl() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "128";
+                                          "blue" => "0", "1023");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        let read_all = || harness.iter().map(|h| h.get("brightness")).collect::<Vec<_>>();
+
+        led.set_color(Color::from_hsv(43, 200, 180)).expect("set color");
+        let expected = read_all();
+        led.set_color(colors::BLACK).expect("clear color");
+        led.set_color_hsv(43, 200, 180).expect("set color hsv");
+        assert_eq!(expected, read_all());
+
+        led.set_color(Color::from_hsl(193, 127, 127)).expect("set color");
+        let expected = read_all();
+        led.set_color(colors::BLACK).expect("clear color");
+        led.set_color_hsl(193, 127, 127).expect("set color hsl");
+        assert_eq!(expected, read_all());
+    }
+
+    #[test]
+    fn test_set_hue() {
+        let harness = create_rgb_harness!("red" => "0", "255";
+                                          "green" => "0", "255";
+                                          "blue" => "0", "255");
+        let mut led = SysfsRgbLed::from_path(harness[0].path(),
+                                             harness[1].path(),
+                                             harness[2].path())
+            .expect("create sysfs rgb led");
+        led.set_color(Color::from_hsv(0, 192, 200)).expect("set color");
+        led.set_hue(86).expect("set hue");
+        let (hue, saturation, value) = led.color().expect("get color").to_hsv();
+        assert!((hue as i32 - 86).abs() <= 2, "hue {}", hue);
+        assert!((saturation as i32 - 192).abs() <= 2, "saturation {}", saturation);
+        assert!((value as i32 - 200).abs() <= 2, "value {}", value);
+        assert_eq!("200", harness[1].get("brightness"));
+    }
+
+    #[test]
+    fn test_max_brightness_cached() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "128";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(128, led.max_brightness().expect("read max brightness"));
+        fs::remove_file(harness.path().join("max_brightness")).expect("remove max_brightness");
+        assert_eq!(128, led.max_brightness().expect("read cached max brightness"));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_set_brightness_async() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "128";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .expect("create runtime");
+        runtime.block_on(led.set_brightness_async(Brightness::Percent(50)))
+            .expect("set brightness");
+        assert_eq!("64", harness.get("brightness"));
+        runtime.block_on(led.set_brightness_async(Brightness::Full))
+            .expect("set brightness");
+        assert_eq!(Brightness::Absolute(128), led.brightness().expect("get brightness"));
+
+        led.set_min_dwell(Some(Dwell::Wait(Duration::from_millis(50))));
+        let start = Instant::now();
+        led.set_brightness(Brightness::Off).expect("set brightness");
+        runtime.block_on(led.set_brightness_async(Brightness::Full))
+            .expect("set brightness");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        led.set_min_dwell(Some(Dwell::Skip(Duration::from_secs(60))));
+        match runtime.block_on(led.set_brightness_async(Brightness::Off)) {
+            Err(Error(ErrorKind::DwellNotElapsed(_), _)) => {}
+            other => panic!("expected DwellNotElapsed, got {:?}", other),
+        }
+        assert_eq!("128", harness.get("brightness"));
+
+        // A missing attribute is reported the same way as by the sync path
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        fs::remove_file(harness.path().join("max_brightness")).expect("remove max_brightness");
+        match led.max_brightness() {
+            Err(Error(ErrorKind::MissingAttribute(_, ref a), _)) if a == "max_brightness" => {}
+            other => panic!("expected MissingAttribute, got {:?}", other),
+        }
+        match runtime.block_on(led.set_brightness_async(Brightness::Full)) {
+            Err(Error(ErrorKind::MissingAttribute(_, ref a), _)) if a == "max_brightness" => {}
+            other => panic!("expected MissingAttribute, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_brightness_raw() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "200";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        led.set_brightness_raw(123).expect("set raw brightness");
+        assert_eq!("123", harness.get("brightness"));
+        led.set_brightness_raw(300).expect("set raw brightness");
+        assert_eq!("200", harness.get("brightness"));
+
+        led.set_min_dwell(Some(Dwell::Skip(Duration::from_secs(60))));
+        match led.set_brightness_raw(50) {
+            Err(Error(ErrorKind::DwellNotElapsed(_), _)) => {}
+            other => panic!("expected DwellNotElapsed, got {:?}", other),
+        }
+        assert_eq!("200", harness.get("brightness"));
+
+        // Raw writes count as changes for the next `set_brightness`
+        led.set_min_dwell(None);
+        led.set_brightness_raw(50).expect("set raw brightness");
+        led.set_min_dwell(Some(Dwell::Skip(Duration::from_secs(60))));
+        match led.set_brightness(Brightness::Full) {
+            Err(Error(ErrorKind::DwellNotElapsed(_), _)) => {}
+            other => panic!("expected DwellNotElapsed, got {:?}", other),
+        }
+        assert_eq!("50", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_hw_changed_brightness() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "brightness_hw_changed" => "42\n");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(Some(Brightness::Absolute(42)),
+                   led.hw_changed_brightness().expect("read hw changed brightness"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(None, led.hw_changed_brightness().expect("read hw changed brightness"));
+    }
+
+    #[test]
+    fn test_set_brightness_if_changed() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "128";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert!(!led.set_brightness_if_changed(Brightness::Absolute(128)).expect("set brightness"));
+        assert!(led.set_brightness_if_changed(Brightness::Full).expect("set brightness"));
+        assert_eq!("255", harness.get("brightness"));
+        assert!(!led.set_brightness_if_changed(Brightness::Percent(100)).expect("set brightness"));
+    }
+
+    #[test]
+    fn test_drive_from() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let cancel = AtomicBool::new(false);
+        let mut samples = 0;
+        led.drive_from(|| {
+                           samples += 1;
+                           if samples == 5 {
+                               cancel.store(true, Ordering::SeqCst);
+                           }
+                           Brightness::Absolute(samples * 10)
+                       },
+                       Duration::from_millis(1),
+                       &cancel)
+            .expect("drive brightness");
+        assert_eq!(5, samples);
+        assert_eq!("50", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_available_triggers() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "none [timer] heartbeat\n");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert_eq!(vec!["none", "timer", "heartbeat"],
+                   led.available_triggers().expect("read triggers"));
+
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        assert!(led.available_triggers().expect("read triggers").is_empty());
+    }
+
+    #[test]
+    fn test_trigger_supports() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer cpu3 mmc0 disk-activity\n");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let supports = |name: &str| led.trigger_supports(name).expect("check trigger");
+        assert!(supports("timer"));
+        assert!(supports("cpu"));
+        assert!(supports("cpu3"));
+        assert!(!supports("cpu1"));
+        assert!(supports("mmc"));
+        assert!(supports("disk-activity"));
+        assert!(!supports("disk"));
+        assert!(!supports("heartbeat"));
+    }
+
+    #[test]
+    fn test_hw_blink() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] timer";
+                                        "delay_on" => "0";
+                                        "delay_off" => "0");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let start = Instant::now();
+        led.hw_blink(Duration::from_millis(100), Duration::from_millis(900), 10)
+            .expect("blink");
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert_eq!("timer", harness.get("trigger"));
+        assert_eq!("100", harness.get("delay_on"));
+        assert_eq!("900", harness.get("delay_off"));
+
+        // Without the timer trigger, blinking happens in software
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none] heartbeat");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let start = Instant::now();
+        led.hw_blink(Duration::from_millis(5), Duration::from_millis(5), 2).expect("blink");
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert_eq!("[none] heartbeat", harness.get("trigger"));
+        assert_eq!("0", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_dither() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let dither = Dither::new(0.5);
+        led.dither(&dither, 4, Duration::from_millis(0)).expect("dither");
+        assert_eq!("255", harness.get("brightness"));
+        led.dither(&dither, 3, Duration::from_millis(0)).expect("dither");
+        assert_eq!("0", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_min_dwell() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let mut led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+
+        led.set_min_dwell(Some(Dwell::Wait(Duration::from_millis(50))));
+        let start = Instant::now();
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        led.set_brightness(Brightness::Off).expect("set brightness");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!("0", harness.get("brightness"));
+
+        led.set_min_dwell(Some(Dwell::Skip(Duration::from_secs(60))));
+        match led.set_brightness(Brightness::Full) {
+            Err(Error(ErrorKind::DwellNotElapsed(_), _)) => {}
+            other => panic!("expected DwellNotElapsed, got {:?}", other),
+        }
+        assert_eq!("0", harness.get("brightness"));
+
+        led.set_min_dwell(None);
+        led.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!("255", harness.get("brightness"));
+
+        // Writes through a pool are held to the dwell of earlier changes
+        led.set_min_dwell(Some(Dwell::Skip(Duration::from_secs(60))));
+        let mut pool = LedPool::new(vec![led]).expect("create led pool");
+        match pool.set(0, Brightness::Off) {
+            Err(Error(ErrorKind::DwellNotElapsed(_), _)) => {}
+            other => panic!("expected DwellNotElapsed, got {:?}", other),
+        }
+        assert_eq!("255", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_pwm_led() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "1";
+                                        "max_brightness" => "1";
+                                        "trigger" => "[none]");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let mut pwm = PwmLed::new(led);
+        assert_eq!(Brightness::Absolute(0), pwm.brightness().expect("get brightness"));
+
+        pwm.set_brightness(Brightness::Percent(50)).expect("set brightness");
+        assert_eq!(127, pwm.duty.load(Ordering::SeqCst));
+        assert_eq!(Brightness::Absolute(127), pwm.brightness().expect("get brightness"));
+        pwm.set_brightness(Brightness::Full).expect("set brightness");
+        assert_eq!(255, pwm.duty.load(Ordering::SeqCst));
+
+        // Dropping waits for the thread, which turns the LED off on its way out
+        drop(pwm);
+        assert_eq!("0", harness.get("brightness"));
+    }
+
+    #[test]
+    fn test_shared_led() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "255";
+                                        "trigger" => "[none]");
+        let led = SharedLed::new(SysfsLed::from_path(harness.path()).expect("create sysfs led"));
+        let threads: Vec<_> = [10, 20]
+            .iter()
+            .map(|&value| {
+                let mut led = led.clone();
+                thread::spawn(move || for _ in 0..100 {
+                    led.set_brightness(Brightness::Absolute(value)).expect("set brightness");
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("join writer");
+        }
+
+        let value = harness.get("brightness");
+        assert!(value == "10" || value == "20", "unexpected brightness {}", value);
+        assert_eq!(Brightness::Absolute(value.parse().unwrap()),
+                   led.brightness().expect("get brightness"));
+        assert_eq!(255, led.max_brightness().expect("get max brightness"));
+    }
+
+    #[cfg(feature = "inotify")]
+    #[test]
+    fn test_watch_trigger() {
+        let harness = create_sysfs_dir!("sysfs_led_test";
+                                        "brightness" => "0";
+                                        "max_brightness" => "128";
+                                        "trigger" => "[none] timer heartbeat");
+        let led = SysfsLed::from_path(harness.path()).expect("create sysfs led");
+        let mut changes = led.watch_trigger().expect("watch trigger");
+        fs::write(harness.path().join("trigger"), "none [timer] heartbeat")
+            .expect("write trigger");
+        assert_eq!("timer", changes.next().expect("trigger change").expect("read trigger"));
+        fs::write(harness.path().join("trigger"), "none timer [heartbeat]")
+            .expect("write trigger");
+        assert_eq!("heartbeat",
+                   changes.next().expect("trigger change").expect("read trigger"));
+    }
+}
@@ -0,0 +1,116 @@
+// Copyright (c) 2017 Nick Stevens <nick@bitcurry.com>
+
+//! In-memory LEDs for testing code built on this crate
+//!
+//! These types implement the LED traits without touching sysfs, and record
+//! every change so tests can check what was written.
+
+use crate::colors::{self, Color};
+use crate::errors::*;
+use crate::{Brightness, Led, RgbLed};
+
+/// An in-memory LED implementing `Led` and `RgbLed`
+///
+/// Brightness is stored as an absolute value against a configurable
+/// `max_brightness`, just as a sysfs LED would store it.
+#[derive(Clone, Debug)]
+pub struct MockLed {
+    max_brightness: u32,
+    value: u32,
+    color: Color,
+    brightness_history: Vec<Brightness>,
+    color_history: Vec<Color>,
+}
+
+impl MockLed {
+    /// Create a new `MockLed` that is off, with a `max_brightness` of 255
+    pub fn new() -> MockLed {
+        Self::with_max_brightness(255)
+    }
+
+    /// Create a new `MockLed` that is off, with the given `max_brightness`
+    pub fn with_max_brightness(max_brightness: u32) -> MockLed {
+        MockLed {
+            max_brightness: max_brightness,
+            value: 0,
+            color: colors::BLACK,
+            brightness_history: vec![],
+            color_history: vec![],
+        }
+    }
+
+    /// Get the brightness most recently passed to `set_brightness`
+    pub fn last_brightness(&self) -> Option<Brightness> {
+        self.brightness_history.last().cloned()
+    }
+
+    /// Get every brightness passed to `set_brightness`, oldest first
+    pub fn brightness_history(&self) -> &[Brightness] {
+        &self.brightness_history
+    }
+
+    /// Get every color passed to `set_color`, oldest first
+    pub fn color_history(&self) -> &[Color] {
+        &self.color_history
+    }
+}
+
+impl Default for MockLed {
+    fn default() -> MockLed {
+        MockLed::new()
+    }
+}
+
+impl Led for MockLed {
+    fn brightness(&self) -> Result<Brightness> {
+        Ok(Brightness::Absolute(self.value))
+    }
+
+    fn set_brightness(&mut self, brightness: Brightness) -> Result<()> {
+        self.value = brightness.to_absolute(self.max_brightness);
+        self.brightness_history.push(brightness);
+        Ok(())
+    }
+
+    fn max_brightness(&self) -> Result<u32> {
+        Ok(self.max_brightness)
+    }
+}
+
+impl RgbLed for MockLed {
+    fn color(&self) -> Result<Color> {
+        Ok(self.color)
+    }
+
+    fn set_color(&mut self, color: Color) -> Result<()> {
+        self.color = color;
+        self.color_history.push(color);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_led() {
+        let mut mock = MockLed::with_max_brightness(100);
+        {
+            let led: &mut dyn Led = &mut mock;
+            assert_eq!(Brightness::Absolute(0), led.brightness().expect("get brightness"));
+            assert_eq!(100, led.max_brightness().expect("get max brightness"));
+            led.set_brightness(Brightness::Percent(40)).expect("set brightness");
+            assert_eq!(Brightness::Absolute(40), led.brightness().expect("get brightness"));
+            led.toggle().expect("toggle");
+            assert!(!led.is_on().expect("is on"));
+        }
+        assert_eq!(Some(Brightness::Off), mock.last_brightness());
+        assert_eq!(&[Brightness::Percent(40), Brightness::Off], mock.brightness_history());
+
+        mock.set_color(colors::RED).expect("set color");
+        assert_eq!(colors::RED, mock.color().expect("get color"));
+        assert_eq!(&[colors::RED], mock.color_history());
+        assert_eq!(None, MockLed::new().last_brightness());
+    }
+}
@@ -1,36 +1,364 @@
 // Copyright (c) 2017 Nick Stevens <nick@bitcurry.com>
 
-use errors::*;
+use std::cmp;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::*;
 use super::{SysfsLed, SysfsRgbLed};
 
+/// An LED trigger along with its parameters
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Trigger {
+    /// No trigger, brightness is controlled manually
+    None,
+    /// Blink with `on` and `off` delays given in milliseconds
+    Timer { on: u64, off: u64 },
+    /// Pulse like a heartbeat, optionally inverted
+    Heartbeat { invert: bool },
+    /// Show activity of the given CPU
+    Cpu(u32),
+    /// Any other trigger, by its sysfs name
+    Other(String),
+}
+
+/// A stock kernel LED trigger, by name
+///
+/// Parses the names listed by `SysfsLed::available_triggers`. Per-device
+/// triggers carry their device number, and any name not listed here parses as
+/// `Custom`, so parsing never fails.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KnownTrigger {
+    None,
+    Timer,
+    Heartbeat,
+    Oneshot,
+    /// Activity of one CPU (`cpuN`), or of any CPU (`cpu`)
+    Cpu(Option<u32>),
+    Netdev,
+    /// Activity of an MMC host (`mmcN`)
+    Mmc(u32),
+    Gpio,
+    DefaultOn,
+    Backlight,
+    DiskActivity,
+    Pattern,
+    /// Overall CPU load (`activity`)
+    Activity,
+    /// Any other trigger, by its sysfs name
+    Custom(String),
+}
+
+impl FromStr for KnownTrigger {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<KnownTrigger> {
+        Ok(match name {
+            "none" => KnownTrigger::None,
+            "timer" => KnownTrigger::Timer,
+            "heartbeat" => KnownTrigger::Heartbeat,
+            "oneshot" => KnownTrigger::Oneshot,
+            "cpu" => KnownTrigger::Cpu(None),
+            "netdev" => KnownTrigger::Netdev,
+            "gpio" => KnownTrigger::Gpio,
+            "default-on" => KnownTrigger::DefaultOn,
+            "backlight" => KnownTrigger::Backlight,
+            "disk-activity" => KnownTrigger::DiskActivity,
+            "pattern" => KnownTrigger::Pattern,
+            "activity" => KnownTrigger::Activity,
+            _ => {
+                match (cpu_number(name), numbered(name, "mmc")) {
+                    (Some(cpu), _) => KnownTrigger::Cpu(Some(cpu)),
+                    (_, Some(mmc)) => KnownTrigger::Mmc(mmc),
+                    _ => KnownTrigger::Custom(name.into()),
+                }
+            }
+        })
+    }
+}
+
+impl fmt::Display for KnownTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KnownTrigger::None => write!(f, "none"),
+            KnownTrigger::Timer => write!(f, "timer"),
+            KnownTrigger::Heartbeat => write!(f, "heartbeat"),
+            KnownTrigger::Oneshot => write!(f, "oneshot"),
+            KnownTrigger::Cpu(None) => write!(f, "cpu"),
+            KnownTrigger::Cpu(Some(cpu)) => write!(f, "cpu{}", cpu),
+            KnownTrigger::Netdev => write!(f, "netdev"),
+            KnownTrigger::Mmc(mmc) => write!(f, "mmc{}", mmc),
+            KnownTrigger::Gpio => write!(f, "gpio"),
+            KnownTrigger::DefaultOn => write!(f, "default-on"),
+            KnownTrigger::Backlight => write!(f, "backlight"),
+            KnownTrigger::DiskActivity => write!(f, "disk-activity"),
+            KnownTrigger::Pattern => write!(f, "pattern"),
+            KnownTrigger::Activity => write!(f, "activity"),
+            KnownTrigger::Custom(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl SysfsLed {
+    /// Read the currently active trigger along with its parameters
+    ///
+    /// Parameters are read from the trigger's attribute files for triggers
+    /// this crate knows about; any other trigger is returned as
+    /// `Trigger::Other` with its name. Devices without trigger support always
+    /// report `Trigger::None`.
+    pub fn active_trigger(&self) -> Result<Trigger> {
+        let name = self.active_trigger_name()?;
+        Ok(match name.as_str() {
+            "none" => Trigger::None,
+            "timer" => {
+                let (on, off) = self.read_timer_delays()?;
+                Trigger::Timer { on: on, off: off }
+            }
+            "heartbeat" => {
+                Trigger::Heartbeat { invert: self.sysfs_read_file("invert")? != "0" }
+            }
+            _ => {
+                match cpu_number(&name) {
+                    Some(cpu) => Trigger::Cpu(cpu),
+                    None => Trigger::Other(name.clone()),
+                }
+            }
+        })
+    }
+
+    /// Deactivate the current trigger and clear its parameters
+    ///
+    /// Any known trigger parameter files that exist (`delay_on`, `delay_off`,
+    /// and `invert`) are zeroed before the trigger is set to `none`, giving a
+    /// clean slate before configuring a new trigger.
+    pub fn reset_trigger(&mut self) -> Result<()> {
+        for file in &["delay_on", "delay_off", "invert"] {
+            if self.device_path.join(file).is_file() {
+                self.sysfs_write_file(file, "0")?;
+            }
+        }
+        self.sysfs_write_trigger("none")
+    }
+
+    /// Read the `(delay_on, delay_off)` delays of the timer trigger, in
+    /// milliseconds
+    ///
+    /// The kernel only creates the delay files while the timer trigger is
+    /// active, so this fails with `TriggerNotActive` if they are absent.
+    /// Unlike `TriggerTimer::current_timings`, the `trigger` file itself is
+    /// not consulted.
+    pub fn timer_delays(&self) -> Result<(u64, u64)> {
+        for file in &["delay_on", "delay_off"] {
+            if !self.device_path.join(file).is_file() {
+                bail!(ErrorKind::TriggerNotActive("timer".into()));
+            }
+        }
+        self.read_timer_delays()
+    }
+
+    /// List the triggers the kernel offers for this LED
+    ///
+    /// Devices without trigger support have no triggers available.
+    pub fn available_triggers(&self) -> Result<Vec<String>> {
+        if !self.has_trigger {
+            return Ok(vec![]);
+        }
+        Ok(self.sysfs_read_file("trigger")?
+            .split_whitespace()
+            .map(|t| t.trim_start_matches('[').trim_end_matches(']').to_string())
+            .collect())
+    }
+
+    /// Check whether the kernel offers a trigger for this LED
+    ///
+    /// A base name also matches its numbered family, so `cpu` is supported if
+    /// any of `cpu0`, `cpu1`, and so on are available.
+    pub fn trigger_supports(&self, name: &str) -> Result<bool> {
+        Ok(self.available_triggers()?.iter().any(|trigger| {
+            trigger == name || numbered(trigger, name).is_some()
+        }))
+    }
+
+    /// Watch for changes to the active trigger
+    ///
+    /// Returns a blocking iterator that waits for the `trigger` file to be
+    /// written, by this or any other process, and yields the name of the newly
+    /// active trigger each time. Requires the `inotify` feature.
+    #[cfg(feature = "inotify")]
+    pub fn watch_trigger(&self) -> Result<impl Iterator<Item = Result<String>> + '_> {
+        let mut inotify = inotify::Inotify::init()?;
+        inotify.add_watch(self.device_path.join("trigger"), inotify::WatchMask::MODIFY)?;
+        Ok(TriggerWatch {
+            led: self,
+            inotify: inotify,
+            buffer: [0; 1024],
+        })
+    }
+
+    // Name of the active trigger, which is always "none" for devices without
+    // trigger support
+    fn active_trigger_name(&self) -> Result<String> {
+        if !self.has_trigger {
+            return Ok("none".into());
+        }
+        let triggers = self.sysfs_read_file("trigger")?;
+        match parse_active_trigger(&triggers) {
+            Some(name) => Ok(name.into()),
+            None => bail!(ErrorKind::UnsupportedTrigger(triggers.clone())),
+        }
+    }
+
+    // Read the `(delay_on, delay_off)` files of the timer trigger, leaving it to
+    // callers to check that the trigger is active
+    fn read_timer_delays(&self) -> Result<(u64, u64)> {
+        Ok((self.sysfs_read_value("delay_on")?,
+            self.sysfs_read_value("delay_off")?))
+    }
+
+    // Activate a trigger, failing cleanly for devices without trigger support
+    fn sysfs_write_trigger(&self, trigger: &str) -> Result<()> {
+        if !self.has_trigger {
+            bail!(ErrorKind::UnsupportedTrigger(trigger.into()));
+        }
+        self.sysfs_write_file("trigger", trigger)
+    }
+
+    // Activate a trigger only if the kernel lists it, for triggers whose
+    // driver may not be built or loaded
+    fn sysfs_write_available_trigger(&self, trigger: &str) -> Result<()> {
+        if !self.available_triggers()?.iter().any(|t| t == trigger) {
+            bail!(ErrorKind::UnsupportedTrigger(trigger.into()));
+        }
+        self.sysfs_write_file("trigger", trigger)
+    }
+}
+
+// Iterator returned by `SysfsLed::watch_trigger`
+#[cfg(feature = "inotify")]
+struct TriggerWatch<'a> {
+    led: &'a SysfsLed,
+    inotify: inotify::Inotify,
+    buffer: [u8; 1024],
+}
+
+#[cfg(feature = "inotify")]
+impl<'a> Iterator for TriggerWatch<'a> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Result<String>> {
+        // Several writes may be reported at once; they all end up in the same
+        // active trigger, so a single read afterwards covers them
+        if let Err(e) = self.inotify.read_events_blocking(&mut self.buffer) {
+            return Some(Err(e.into()));
+        }
+        Some(self.led.active_trigger_name())
+    }
+}
+
+// Find the active trigger in the contents of a `trigger` file, which lists all
+// available triggers with the active one in square brackets
+fn parse_active_trigger(triggers: &str) -> Option<&str> {
+    triggers.split_whitespace()
+        .find(|t| t.starts_with('[') && t.ends_with(']'))
+        .map(|t| &t[1..t.len() - 1])
+}
+
+// Parse the CPU number out of a `cpuN` trigger name
+fn cpu_number(name: &str) -> Option<u32> {
+    numbered(name, "cpu")
+}
+
+// Parse the device number out of a trigger name like `mmcN`. The number must
+// be plain digits, since `parse` would also accept a sign that doesn't survive
+// formatting the name back.
+fn numbered(name: &str, prefix: &str) -> Option<u32> {
+    if !name.starts_with(prefix) {
+        return None;
+    }
+    let number = &name[prefix.len()..];
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u32>().ok()
+}
+
+/// Apply any `Trigger`, along with its parameters
+pub trait SetTrigger {
+    fn set_trigger(&mut self, trigger: &Trigger) -> Result<()>;
+}
+
+impl SetTrigger for SysfsLed {
+    fn set_trigger(&mut self, trigger: &Trigger) -> Result<()> {
+        match *trigger {
+            Trigger::None => self.none(),
+            Trigger::Timer { on, off } => self.timer(on, off),
+            Trigger::Heartbeat { invert } => self.heartbeat(invert),
+            Trigger::Cpu(cpu) => self.cpu(cpu),
+            Trigger::Other(ref name) => self.sysfs_write_trigger(name),
+        }
+    }
+}
+
 pub trait TriggerNone {
     fn none(&mut self) -> Result<()>;
 }
 
+impl SetTrigger for SysfsRgbLed {
+    fn set_trigger(&mut self, trigger: &Trigger) -> Result<()> {
+        self.red.set_trigger(trigger)
+            .and(self.green.set_trigger(trigger))
+            .and(self.blue.set_trigger(trigger))
+    }
+}
+
 impl TriggerNone for SysfsLed {
     fn none(&mut self) -> Result<()> {
-        self.sysfs_write_file("trigger", "none")
+        self.sysfs_write_trigger("none")
     }
 }
 
 impl TriggerNone for SysfsRgbLed {
     fn none(&mut self) -> Result<()> {
-        self.red.sysfs_write_file("trigger", "none")
-            .and(self.green.sysfs_write_file("trigger", "none"))
-            .and(self.blue.sysfs_write_file("trigger", "none"))
+        self.red.sysfs_write_trigger("none")
+            .and(self.green.sysfs_write_trigger("none"))
+            .and(self.blue.sysfs_write_trigger("none"))
     }
 }
 
 pub trait TriggerTimer {
     fn timer(&mut self, delay_on: u64, delay_off: u64) -> Result<()>;
+    /// Read the `(delay_on, delay_off)` timings of the active timer trigger
+    fn current_timings(&self) -> Result<(u64, u64)>;
 }
 
 impl TriggerTimer for SysfsLed {
     fn timer(&mut self, delay_on: u64, delay_off: u64) -> Result<()> {
-        self.sysfs_write_file("trigger", "timer")
+        self.sysfs_write_trigger("timer")
             .and(self.sysfs_write_file("delay_on", &format!("{}", delay_on)))
             .and(self.sysfs_write_file("delay_off", &format!("{}", delay_off)))
     }
+
+    fn current_timings(&self) -> Result<(u64, u64)> {
+        if self.active_trigger_name()? != "timer" {
+            bail!(ErrorKind::TriggerNotActive("timer".into()));
+        }
+        self.read_timer_delays()
+    }
+}
+
+impl TriggerTimer for SysfsRgbLed {
+    fn timer(&mut self, delay_on: u64, delay_off: u64) -> Result<()> {
+        self.red.timer(delay_on, delay_off)
+            .and(self.green.timer(delay_on, delay_off))
+            .and(self.blue.timer(delay_on, delay_off))
+    }
+
+    // The channels are configured together, so red speaks for all three
+    fn current_timings(&self) -> Result<(u64, u64)> {
+        self.red.current_timings()
+    }
 }
 
 pub trait TriggerHeartbeat {
@@ -39,17 +367,126 @@ pub trait TriggerHeartbeat {
 
 impl TriggerHeartbeat for SysfsLed {
     fn heartbeat(&mut self, invert: bool) -> Result<()> {
-        self.sysfs_write_file("trigger", "heartbeat")
+        self.sysfs_write_trigger("heartbeat")
             .and(self.sysfs_write_file("invert", if invert { "1" } else { "0" }))
     }
 }
 
+impl TriggerHeartbeat for SysfsRgbLed {
+    fn heartbeat(&mut self, invert: bool) -> Result<()> {
+        self.red.heartbeat(invert)
+            .and(self.green.heartbeat(invert))
+            .and(self.blue.heartbeat(invert))
+    }
+}
+
 pub trait TriggerCpu {
     fn cpu(&mut self, cpu: u32) -> Result<()>;
+    /// Show activity of any CPU
+    fn cpu_all(&mut self) -> Result<()>;
+    /// Show overall CPU load, flashing faster as the system gets busier
+    fn activity(&mut self) -> Result<()>;
 }
 
 impl TriggerCpu for SysfsLed {
     fn cpu(&mut self, cpu: u32) -> Result<()> {
-        self.sysfs_write_file("trigger", &format!("cpu{}", cpu))
+        self.sysfs_write_trigger(&format!("cpu{}", cpu))
+    }
+
+    fn cpu_all(&mut self) -> Result<()> {
+        self.sysfs_write_available_trigger("cpu")
+    }
+
+    fn activity(&mut self) -> Result<()> {
+        self.sysfs_write_available_trigger("activity")
+    }
+}
+
+pub trait TriggerDisk {
+    /// Show any disk activity
+    fn disk_activity(&mut self) -> Result<()>;
+    /// Show disk reads
+    fn disk_read(&mut self) -> Result<()>;
+    /// Show disk writes
+    fn disk_write(&mut self) -> Result<()>;
+}
+
+impl TriggerDisk for SysfsLed {
+    fn disk_activity(&mut self) -> Result<()> {
+        self.sysfs_write_available_trigger("disk-activity")
+    }
+
+    fn disk_read(&mut self) -> Result<()> {
+        self.sysfs_write_available_trigger("disk-read")
+    }
+
+    fn disk_write(&mut self) -> Result<()> {
+        self.sysfs_write_available_trigger("disk-write")
+    }
+}
+
+pub trait TriggerBacklight {
+    /// Follow the framebuffer blanking state
+    fn backlight(&mut self) -> Result<()>;
+}
+
+impl TriggerBacklight for SysfsLed {
+    fn backlight(&mut self) -> Result<()> {
+        self.sysfs_write_available_trigger("backlight")
+    }
+}
+
+pub trait TriggerGpio {
+    /// Follow the state of a GPIO input, lighting the LED at
+    /// `desired_brightness` while the input is active
+    fn gpio(&mut self, gpio_num: u32, inverted: bool, desired_brightness: u32) -> Result<()>;
+}
+
+impl TriggerGpio for SysfsLed {
+    fn gpio(&mut self, gpio_num: u32, inverted: bool, desired_brightness: u32) -> Result<()> {
+        let desired_brightness = cmp::min(desired_brightness, self.max_brightness()?);
+        self.sysfs_write_trigger("gpio")
+            .and(self.sysfs_write_file("gpio", &format!("{}", gpio_num)))
+            .and(self.sysfs_write_file("inverted", if inverted { "1" } else { "0" }))
+            .and(self.sysfs_write_file("desired_brightness", &format!("{}", desired_brightness)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_known_trigger() {
+        let vectors = vec![("none", KnownTrigger::None),
+                           ("default-on", KnownTrigger::DefaultOn),
+                           ("disk-activity", KnownTrigger::DiskActivity),
+                           ("cpu", KnownTrigger::Cpu(None)),
+                           ("cpu0", KnownTrigger::Cpu(Some(0))),
+                           ("activity", KnownTrigger::Activity),
+                           ("mmc1", KnownTrigger::Mmc(1)),
+                           ("mmc", KnownTrigger::Custom("mmc".into())),
+                           ("cpu+1", KnownTrigger::Custom("cpu+1".into())),
+                           ("mmc+0", KnownTrigger::Custom("mmc+0".into())),
+                           ("phy0rx", KnownTrigger::Custom("phy0rx".into()))];
+        for (name, expected) in vectors {
+            let trigger = name.parse::<KnownTrigger>().expect("parse trigger");
+            assert_eq!(expected, trigger);
+            assert_eq!(name, trigger.to_string());
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let vectors = vec![Trigger::None,
+                           Trigger::Timer { on: 100, off: 900 },
+                           Trigger::Heartbeat { invert: true },
+                           Trigger::Cpu(2),
+                           Trigger::Other("mmc0".into())];
+        for trigger in vectors {
+            let json = serde_json::to_string(&trigger).expect("serialize trigger");
+            assert_eq!(trigger, serde_json::from_str(&json).expect("deserialize trigger"));
+        }
     }
 }